@@ -1,12 +1,68 @@
 use crate::lawscape_api_server_error::ApiServerError;
-use axum::{Router, extract::Query, http::Method, response::Json, routing::get};
-use lawscape_core::{LegalDocumentDependencies, LegalDocumentsRegistory};
+use crate::v2::{
+    DocumentDto, Envelope, LawArticleDto, PaginationDto, PrecedentDto, ResultShapeDto,
+    SearchGroupDto, SearchHitDto, SearchRequestDto, SearchResultsDto, V2Error,
+};
+use axum::{
+    Router,
+    extract::{Path, Query, State},
+    http::Method,
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+};
+use chrono::NaiveDate;
+use lawscape_core::definition::DefinedTerm;
+use lawscape_core::delegation::DelegationNode;
+use lawscape_core::embedding::{Embedder, HashedNgramEmbedder};
+use lawscape_core::graph::{
+    GraphDirection, GraphEdgeKind, GraphFormat, GraphPath, GraphTraversal, LegalGraph,
+};
+use lawscape_core::{
+    Annotation, CitationResolution, DependenciesOrder, HighlightOptions, Law, LawNamesCache,
+    LawscapeCoreError, LegalDocument, LegalDocumentDependencies, LegalDocumentSearchResult,
+    LegalDocumentType, LegalDocumentsRegistory, Precedent, PrecedentCitations, SearchFilter,
+    SearchOptions, SearchSort, StatutePrecedent, Suggestion,
+};
 use reqwest::header::CONTENT_TYPE;
-use std::collections::HashMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::{error, info};
 
+/// 全てのルートで共有する設定
+struct AppState {
+    meilisearch_url: String,
+    meilisearch_master_key: String,
+    default_limit: usize,
+    default_search_cancel_score: f64,
+    /// 検索語を埋め込みベクトルに変換する方法。無い場合はハイブリッド検索を受け付けない
+    embedder: Option<Arc<dyn Embedder>>,
+    /// 法令名の一覧はリクエストの間で使い回す
+    law_names_cache: LawNamesCache,
+}
+
+impl AppState {
+    fn registry(&self) -> Result<LegalDocumentsRegistory, ApiServerError> {
+        LegalDocumentsRegistory::new(&self.meilisearch_url, &self.meilisearch_master_key).map_err(
+            |e| {
+                error!("failed at LegalDocumentsRegistory::new; {e}");
+                ApiServerError::MeilisearchError
+            },
+        )
+    }
+
+    /// 検索語を埋め込みベクトルに変換する方法を設定したレジストリを生成する
+    fn search_registry(&self) -> Result<LegalDocumentsRegistory, ApiServerError> {
+        let registry = self.registry()?;
+        Ok(match &self.embedder {
+            Some(embedder) => registry.with_embedder(embedder.clone()),
+            None => registry,
+        })
+    }
+}
+
 /// ログを出力するための設定など
 async fn init_logger() -> Result<(), ApiServerError> {
     let subscriber = tracing_subscriber::fmt()
@@ -23,9 +79,19 @@ pub async fn app(
     meilisearch_master_key: String,
     default_limit: usize,
     default_search_cancel_score: f64,
+    embedding: bool,
 ) -> Result<(), ApiServerError> {
     init_logger().await?;
 
+    let state = Arc::new(AppState {
+        meilisearch_url,
+        meilisearch_master_key,
+        default_limit,
+        default_search_cancel_score,
+        embedder: embedding.then(|| Arc::new(HashedNgramEmbedder::default()) as Arc<dyn Embedder>),
+        law_names_cache: LawNamesCache::default(),
+    });
+
     let app = Router::new()
         .route(
             "/v1/ping",
@@ -36,34 +102,236 @@ pub async fn app(
         )
         .route(
             "/v1/search",
-            get(move |query: Query<HashMap<String, String>>| {
-                let search_word = query.0.get("word").cloned().unwrap_or_default();
-                let limit = query
-                    .0
-                    .get("limit")
-                    .and_then(|s| s.parse::<usize>().ok())
-                    .unwrap_or(default_limit);
-                let search_cancel_score = query
-                    .0
-                    .get("cancel_score")
-                    .and_then(|s| s.parse::<f64>().ok())
-                    .unwrap_or(default_search_cancel_score);
-                info!("GET /v1/search: {search_word}, cancel_score: {search_cancel_score}, limit: {limit}");
-                v1_get_search(
-                    search_word,
-                    meilisearch_url,
-                    meilisearch_master_key,
-                    limit,
-                    search_cancel_score,
-                )
-            }),
+            get(
+                |State(state): State<Arc<AppState>>,
+                 query: Query<HashMap<String, String>>| async move {
+                    let search_params = SearchParams::from_query(&query.0, &state)?;
+                    info!("GET /v1/search: {search_params:?}");
+                    v1_search(search_params, &state).await
+                },
+            )
+            .post(
+                |State(state): State<Arc<AppState>>,
+                 Json(request): Json<V1SearchRequest>| async move {
+                    let search_params = request.into_search_params(&state);
+                    info!("POST /v1/search: {search_params:?}");
+                    v1_search(search_params, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/graph",
+            get(
+                |State(state): State<Arc<AppState>>,
+                 query: Query<HashMap<String, String>>| async move {
+                    let search_params = SearchParams::from_query(&query.0, &state)?;
+                    let format = query.0.get("format").cloned();
+                    info!("GET /v1/graph: {search_params:?}, format: {format:?}");
+                    v1_get_graph(search_params, format, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/graph/neighborhood",
+            get(
+                |State(state): State<Arc<AppState>>,
+                 query: Query<HashMap<String, String>>| async move {
+                    info!("GET /v1/graph/neighborhood: {:?}", query.0);
+                    v1_get_graph_neighborhood(query.0, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/graph/path",
+            get(
+                |State(state): State<Arc<AppState>>,
+                 query: Query<HashMap<String, String>>| async move {
+                    info!("GET /v1/graph/path: {:?}", query.0);
+                    v1_get_graph_path(query.0, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/documents/{id}",
+            get(
+                |State(state): State<Arc<AppState>>, Path(id): Path<String>| async move {
+                    info!("GET /v1/documents/{id}");
+                    v1_get_document(id, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/documents/{id}/similar",
+            get(
+                |State(state): State<Arc<AppState>>,
+                 Path(id): Path<String>,
+                 query: Query<HashMap<String, String>>| async move {
+                    let search_params = SearchParams::from_query(&query.0, &state)?;
+                    info!("GET /v1/documents/{id}/similar: {search_params:?}");
+                    v1_get_similar(id, search_params.options, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/laws/{law_id}",
+            get(
+                |State(state): State<Arc<AppState>>, Path(law_id): Path<String>| async move {
+                    info!("GET /v1/laws/{law_id}");
+                    v1_get_law(law_id, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/laws/{law_id}/definitions",
+            get(
+                |State(state): State<Arc<AppState>>, Path(law_id): Path<String>| async move {
+                    info!("GET /v1/laws/{law_id}/definitions");
+                    v1_get_law_definitions(law_id, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/definitions",
+            get(
+                |State(state): State<Arc<AppState>>,
+                 query: Query<HashMap<String, String>>| async move {
+                    info!("GET /v1/definitions: {:?}", query.0);
+                    v1_find_definitions(query.0, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/laws/{law_id}/delegations",
+            get(
+                |State(state): State<Arc<AppState>>, Path(law_id): Path<String>| async move {
+                    info!("GET /v1/laws/{law_id}/delegations");
+                    v1_get_delegation_tree(law_id, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/precedents",
+            get(
+                |State(state): State<Arc<AppState>>,
+                 query: Query<HashMap<String, String>>| async move {
+                    info!("GET /v1/precedents: {:?}", query.0);
+                    v1_get_precedents_by_statute(query.0, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/precedents/{id}",
+            get(
+                |State(state): State<Arc<AppState>>, Path(id): Path<String>| async move {
+                    info!("GET /v1/precedents/{id}");
+                    v1_get_precedent(id, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/precedents/{id}/sections",
+            get(
+                |State(state): State<Arc<AppState>>, Path(id): Path<String>| async move {
+                    info!("GET /v1/precedents/{id}/sections");
+                    v1_get_precedent_sections(id, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/precedents/{id}/citations",
+            get(
+                |State(state): State<Arc<AppState>>,
+                 Path(id): Path<String>,
+                 query: Query<HashMap<String, String>>| async move {
+                    info!("GET /v1/precedents/{id}/citations: {:?}", query.0);
+                    v1_get_precedent_citations(id, query.0, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/resolve",
+            get(
+                |State(state): State<Arc<AppState>>,
+                 query: Query<HashMap<String, String>>| async move {
+                    let cite = query.0.get("cite").cloned().unwrap_or_default();
+                    info!("GET /v1/resolve: {cite}");
+                    v1_get_resolve(cite, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/annotate",
+            post(
+                |State(state): State<Arc<AppState>>,
+                 Json(request): Json<V1AnnotateRequest>| async move {
+                    info!("POST /v1/annotate: text size: {}", request.text.len());
+                    v1_post_annotate(request, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v1/suggest",
+            get(
+                |State(state): State<Arc<AppState>>,
+                 query: Query<HashMap<String, String>>| async move {
+                    info!("GET /v1/suggest: {:?}", query.0);
+                    v1_get_suggest(query.0, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v2/search",
+            get(
+                |State(state): State<Arc<AppState>>,
+                 query: Query<HashMap<String, String>>| async move {
+                    let search_params = SearchParams::from_query(&query.0, &state)?;
+                    info!("GET /v2/search: {search_params:?}");
+                    v2_search(search_params, &state).await
+                },
+            )
+            .post(
+                |State(state): State<Arc<AppState>>,
+                 Json(request): Json<SearchRequestDto>| async move {
+                    let search_params = SearchParams::from_v2_request(request, &state);
+                    info!("POST /v2/search: {search_params:?}");
+                    v2_search(search_params, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v2/documents/{id}",
+            get(
+                |State(state): State<Arc<AppState>>, Path(id): Path<String>| async move {
+                    info!("GET /v2/documents/{id}");
+                    v2_get_document(id, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v2/laws/{law_id}",
+            get(
+                |State(state): State<Arc<AppState>>, Path(law_id): Path<String>| async move {
+                    info!("GET /v2/laws/{law_id}");
+                    v2_get_law(law_id, &state).await
+                },
+            ),
+        )
+        .route(
+            "/v2/precedents/{id}",
+            get(
+                |State(state): State<Arc<AppState>>, Path(id): Path<String>| async move {
+                    info!("GET /v2/precedents/{id}");
+                    v2_get_precedent(id, &state).await
+                },
+            ),
         )
         .layer(
             CorsLayer::new()
-                .allow_methods([Method::GET])
+                .allow_methods([Method::GET, Method::POST])
                 .allow_headers([CONTENT_TYPE])
                 .allow_origin(Any),
-        );
+        )
+        .with_state(state);
     let listener = tokio::net::TcpListener::bind(bind)
         .await
         .map_err(|_| ApiServerError::AxumError)?;
@@ -78,34 +346,932 @@ pub async fn app(
     Ok(())
 }
 
-async fn v1_get_search(
+/// 検索結果の形式
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ResultShape {
+    /// 法令ごとにまとめ、依存関係を付ける
+    #[default]
+    Grouped,
+    /// 検索結果をそのまま並べる
+    Flat,
+}
+
+/// 検索時に指定するパラメータ
+#[derive(Debug, Clone)]
+struct SearchParams {
     word: String,
-    meilisearch_url: String,
-    meilisearch_master_key: String,
-    limit: usize,
-    search_cancel_score: f64,
-) -> Result<Json<Vec<LegalDocumentDependencies>>, ApiServerError> {
-    let search_registry = LegalDocumentsRegistory::new(&meilisearch_url, &meilisearch_master_key)
-        .map_err(|e| {
-        error!("failed at LegalDocumentsRegistory::new; {e}");
-        ApiServerError::MeilisearchError
-    })?;
-    if word.is_empty() {
+    /// 「民法709条」のような引用。指定した条文を参照している判例に絞り込む
+    statute: Option<String>,
+    options: SearchOptions,
+    order: DependenciesOrder,
+    shape: ResultShape,
+}
+
+impl SearchParams {
+    fn from_query(
+        query: &HashMap<String, String>,
+        state: &AppState,
+    ) -> Result<Self, ApiServerError> {
+        let word = query.get("word").cloned().unwrap_or_default();
+        let limit = query
+            .get("limit")
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(state.default_limit);
+        let search_cancel_score = query
+            .get("cancel_score")
+            .and_then(|s| s.parse::<f64>().ok())
+            .unwrap_or(state.default_search_cancel_score);
+        let order = query
+            .get("order")
+            .map(|s| s.parse::<DependenciesOrder>())
+            .transpose()
+            .map_err(|e| {
+                error!("invalid order; {e}");
+                ApiServerError::InvalidParameter
+            })?
+            .unwrap_or_default();
+        let offset = page_to_offset(
+            query.get("page").and_then(|s| s.parse::<usize>().ok()),
+            query.get("offset").and_then(|s| s.parse::<usize>().ok()),
+            limit,
+        );
+        let highlight = query
+            .get("highlight")
+            .is_some_and(|s| s == "true")
+            .then(|| {
+                let default = HighlightOptions::default();
+                HighlightOptions {
+                    pre_tag: query
+                        .get("highlight_pre_tag")
+                        .cloned()
+                        .unwrap_or(default.pre_tag),
+                    post_tag: query
+                        .get("highlight_post_tag")
+                        .cloned()
+                        .unwrap_or(default.post_tag),
+                    crop_length: query
+                        .get("crop_length")
+                        .and_then(|s| s.parse::<usize>().ok()),
+                    crop_marker: query
+                        .get("crop_marker")
+                        .cloned()
+                        .unwrap_or(default.crop_marker),
+                }
+            });
+        let facets = query.get("facets").is_some_and(|s| s == "true");
+        let as_of = query
+            .get("as_of")
+            .map(|s| {
+                parse_date_number(s).ok_or_else(|| {
+                    error!("invalid as_of: {s}");
+                    ApiServerError::InvalidParameter
+                })
+            })
+            .transpose()?;
+        let semantic_ratio = query
+            .get("semantic_ratio")
+            .and_then(|s| s.parse::<f32>().ok());
+        let sort = query
+            .get("sort")
+            .map(|s| {
+                s.split(',')
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.parse::<SearchSort>())
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()
+            .map_err(|e| {
+                error!("invalid sort; {e}");
+                ApiServerError::InvalidParameter
+            })?
+            .unwrap_or_default();
+        Ok(Self {
+            word,
+            statute: query.get("statute").cloned(),
+            options: SearchOptions {
+                limit,
+                offset,
+                cancel_score: search_cancel_score,
+                highlight,
+                filter: SearchFilter {
+                    include_repealed: query.get("include_repealed").is_some_and(|s| s == "true"),
+                    include_future: query.get("include_future").is_some_and(|s| s == "true"),
+                    as_of,
+                    ..Default::default()
+                },
+                facets,
+                sort,
+                semantic_ratio,
+                query_syntax: query.get("query_syntax").is_some_and(|s| s == "true"),
+            },
+            order,
+            shape: ResultShape::default(),
+        })
+    }
+
+    /// POST /v2/searchで受け取った検索条件から求める
+    fn from_v2_request(request: SearchRequestDto, state: &AppState) -> Self {
+        let limit = request.limit.unwrap_or(state.default_limit);
+        Self {
+            word: request.query,
+            statute: request.statute,
+            options: SearchOptions {
+                limit,
+                offset: request.offset.unwrap_or(0),
+                cancel_score: request
+                    .cancel_score
+                    .unwrap_or(state.default_search_cancel_score),
+                highlight: request.highlight.map(HighlightOptions::from),
+                filter: request.filter.into(),
+                facets: request.facets,
+                sort: request.sort.into_iter().map(SearchSort::from).collect(),
+                semantic_ratio: request.semantic_ratio,
+                query_syntax: request.query_syntax,
+            },
+            order: request.group_order.into(),
+            shape: match request.shape {
+                ResultShapeDto::Grouped => ResultShape::Grouped,
+                ResultShapeDto::Flat => ResultShape::Flat,
+            },
+        }
+    }
+}
+
+/// `yyyy-mm-dd`の形の日付を`yyyymmdd`の形の数にする。暦に無い日付の場合は`None`
+fn parse_date_number(str: &str) -> Option<usize> {
+    let mut parts = str.splitn(3, '-').map(|s| s.parse::<usize>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    NaiveDate::from_ymd_opt(
+        i32::try_from(year).ok()?,
+        u32::try_from(month).ok()?,
+        u32::try_from(day).ok()?,
+    )?;
+    Some(year * 10000 + month * 100 + day)
+}
+
+/// `yyyy-mm-dd`の形の日付を受け取る
+pub(crate) fn deserialize_date_number<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| {
+            parse_date_number(&s)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid date: {s}")))
+        })
+        .transpose()
+}
+
+/// pageは1始まりで、指定された場合はoffsetより優先する
+fn page_to_offset(page: Option<usize>, offset: Option<usize>, limit: usize) -> usize {
+    page.map(|page| page.saturating_sub(1) * limit)
+        .or(offset)
+        .unwrap_or(0)
+}
+
+/// POST /v1/searchで受け取る検索条件
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct V1SearchRequest {
+    query: String,
+    #[serde(default)]
+    filter: SearchFilter,
+    #[serde(default)]
+    order: DependenciesOrder,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    page: Option<usize>,
+    cancel_score: Option<f64>,
+    highlight: Option<HighlightOptions>,
+    #[serde(default)]
+    facets: bool,
+    #[serde(default)]
+    sort: Vec<SearchSort>,
+    semantic_ratio: Option<f32>,
+    #[serde(default)]
+    shape: ResultShape,
+    /// `yyyy-mm-dd`の形の日付。この日の時点の文書に絞り込む
+    #[serde(default, deserialize_with = "deserialize_date_number")]
+    as_of: Option<usize>,
+    /// `query`を検索式として解析するかどうか
+    #[serde(default)]
+    query_syntax: bool,
+    /// 「民法709条」のような引用。指定した条文を参照している判例に絞り込む
+    statute: Option<String>,
+}
+
+impl V1SearchRequest {
+    fn into_search_params(self, state: &AppState) -> SearchParams {
+        let limit = self.limit.unwrap_or(state.default_limit);
+        SearchParams {
+            word: self.query,
+            statute: self.statute,
+            options: SearchOptions {
+                limit,
+                offset: page_to_offset(self.page, self.offset, limit),
+                cancel_score: self
+                    .cancel_score
+                    .unwrap_or(state.default_search_cancel_score),
+                highlight: self.highlight,
+                filter: SearchFilter {
+                    as_of: self.as_of,
+                    ..self.filter
+                },
+                facets: self.facets,
+                sort: self.sort,
+                semantic_ratio: self.semantic_ratio,
+                query_syntax: self.query_syntax,
+            },
+            order: self.order,
+            shape: self.shape,
+        }
+    }
+}
+
+async fn v1_search(
+    mut search_params: SearchParams,
+    state: &AppState,
+) -> Result<Json<V1SearchResponse>, ApiServerError> {
+    if search_params.word.is_empty() {
         error!("search word is empty");
-        Err(ApiServerError::SearchError)
+        Err(ApiServerError::InvalidParameter)
+    } else if search_params
+        .options
+        .semantic_ratio
+        .is_some_and(|r| !(0.0..=1.0).contains(&r))
+    {
+        error!("semantic_ratio is out of range");
+        Err(ApiServerError::InvalidParameter)
     } else {
+        let search_registry = state.search_registry()?;
+        // 引数を確かめてから、条文の引用を解決するためにmeilisearchへ問い合わせる
+        if let Some(statute) = &search_params.statute {
+            let key = resolve_statute_key(&search_registry, statute).await?;
+            search_params.options.filter.statutes.push(key);
+        }
         let search_result = search_registry
-            .search(&word, limit, search_cancel_score)
+            .search(&search_params.word, &search_params.options)
             .await
-            .map_err(|e| {
-                error!("failed at search; {e}");
-                ApiServerError::SearchError
-            })?;
-        let dependencies_result = lawscape_core::analyze_search_result_dependencies(&search_result);
-        let result = dependencies_result
-            .values()
-            .cloned()
-            .collect::<Vec<LegalDocumentDependencies>>();
-        Ok(Json(result))
+            .map_err(search_error)?;
+        let pagination = Pagination {
+            offset: search_result.offset,
+            limit: search_result.limit,
+            hits: search_result.hits.len(),
+            estimated_total_hits: search_result.estimated_total_hits,
+            next_offset: search_result.next_offset(),
+        };
+        let results = match search_params.shape {
+            ResultShape::Grouped => {
+                let dependencies_result =
+                    lawscape_core::analyze_search_result_dependencies(&search_result.hits);
+                V1SearchResults::Grouped(lawscape_core::sort_search_result_dependencies(
+                    dependencies_result,
+                    search_params.order,
+                ))
+            }
+            ResultShape::Flat => V1SearchResults::Flat(search_result.hits),
+        };
+        Ok(Json(V1SearchResponse {
+            results,
+            pagination,
+            facets: search_result.facets,
+        }))
+    }
+}
+
+/// 検索のエラーをレスポンスのエラーにする。検索式や引数の誤りは400、それ以外は500
+fn search_error(e: LawscapeCoreError) -> ApiServerError {
+    error!("failed at search; {e}");
+    match e {
+        LawscapeCoreError::QuerySyntaxError(message) => ApiServerError::QuerySyntax(message),
+        LawscapeCoreError::InvalidParameter(_) => ApiServerError::InvalidParameter,
+        _ => ApiServerError::SearchError,
+    }
+}
+
+/// 引用から判例を参照している条文で絞り込むための値を求める。法令が登録されていない場合は404
+async fn resolve_statute_key(
+    registry: &LegalDocumentsRegistory,
+    statute: &str,
+) -> Result<String, ApiServerError> {
+    registry
+        .resolve_statute_key(statute)
+        .await
+        .map_err(|e| {
+            error!("failed at resolve_statute_key; {e}");
+            match e {
+                LawscapeCoreError::InvalidParameter(_) => ApiServerError::InvalidParameter,
+                _ => ApiServerError::SearchError,
+            }
+        })?
+        .ok_or(ApiServerError::NotFound)
+}
+
+/// 検索結果の参照関係をグラフとして返す
+async fn v1_get_graph(
+    mut search_params: SearchParams,
+    format: Option<String>,
+    state: &AppState,
+) -> Result<Response, ApiServerError> {
+    let format = format
+        .map(|s| s.parse::<GraphFormat>())
+        .transpose()
+        .map_err(|e| {
+            error!("invalid graph format; {e}");
+            ApiServerError::InvalidParameter
+        })?
+        .unwrap_or_default();
+    if search_params.word.is_empty() {
+        error!("search word is empty");
+        return Err(ApiServerError::InvalidParameter);
+    }
+    let search_registry = state.search_registry()?;
+    if let Some(statute) = &search_params.statute {
+        let key = resolve_statute_key(&search_registry, statute).await?;
+        search_params.options.filter.statutes.push(key);
+    }
+    let search_result = search_registry
+        .search(&search_params.word, &search_params.options)
+        .await
+        .map_err(search_error)?;
+    let dependencies_result =
+        lawscape_core::analyze_search_result_dependencies(&search_result.hits);
+    let body = LegalGraph::from_dependencies(&dependencies_result)
+        .render(format)
+        .map_err(|e| {
+            error!("failed at render graph; {e}");
+            ApiServerError::SearchError
+        })?;
+    Ok(([(CONTENT_TYPE, format.content_type())], body).into_response())
+}
+
+/// 周りのグラフを取得する際にたどる辺の数のデフォルト値
+const DEFAULT_NEIGHBORHOOD_DEPTH: usize = 1;
+/// 経路を探す際にたどる辺の数のデフォルト値
+const DEFAULT_PATH_DEPTH: usize = 3;
+
+/// グラフをたどる際の条件をクエリから求める
+fn graph_traversal_from_query(
+    query: &HashMap<String, String>,
+    depth_key: &str,
+    default_depth: usize,
+) -> Result<GraphTraversal, ApiServerError> {
+    let depth = query
+        .get(depth_key)
+        .map(|s| s.parse::<usize>())
+        .transpose()
+        .map_err(|e| {
+            error!("invalid {depth_key}; {e}");
+            ApiServerError::InvalidParameter
+        })?
+        .unwrap_or(default_depth);
+    if depth == 0 || lawscape_core::GRAPH_MAX_DEPTH < depth {
+        error!("{depth_key} is out of range");
+        return Err(ApiServerError::InvalidParameter);
+    }
+    let direction = query
+        .get("direction")
+        .map(|s| s.parse::<GraphDirection>())
+        .transpose()
+        .map_err(|e| {
+            error!("invalid direction; {e}");
+            ApiServerError::InvalidParameter
+        })?
+        .unwrap_or_default();
+    let edge_kinds = query
+        .get("edge_types")
+        .map(|s| {
+            s.split(',')
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse::<GraphEdgeKind>())
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()
+        .map_err(|e| {
+            error!("invalid edge_types; {e}");
+            ApiServerError::InvalidParameter
+        })?
+        .unwrap_or_default();
+    Ok(GraphTraversal {
+        depth,
+        direction,
+        edge_kinds,
+    })
+}
+
+/// 文書の周りのグラフを返す
+async fn v1_get_graph_neighborhood(
+    query: HashMap<String, String>,
+    state: &AppState,
+) -> Result<Response, ApiServerError> {
+    let id = query.get("id").cloned().unwrap_or_default();
+    if id.is_empty() {
+        error!("id is empty");
+        return Err(ApiServerError::InvalidParameter);
+    }
+    let traversal = graph_traversal_from_query(&query, "depth", DEFAULT_NEIGHBORHOOD_DEPTH)?;
+    let format = query
+        .get("format")
+        .map(|s| s.parse::<GraphFormat>())
+        .transpose()
+        .map_err(|e| {
+            error!("invalid graph format; {e}");
+            ApiServerError::InvalidParameter
+        })?
+        .unwrap_or_default();
+    let registry = state.registry()?;
+    let graph = registry
+        .get_graph_neighborhood(&id, &traversal)
+        .await
+        .map_err(|e| {
+            error!("failed at get_graph_neighborhood; {e}");
+            ApiServerError::MeilisearchError
+        })?
+        .ok_or(ApiServerError::NotFound)?;
+    let body = graph.render(format).map_err(|e| {
+        error!("failed at render graph; {e}");
+        ApiServerError::SearchError
+    })?;
+    Ok(([(CONTENT_TYPE, format.content_type())], body).into_response())
+}
+
+/// 二つの文書を結ぶ最短の経路を返す
+async fn v1_get_graph_path(
+    query: HashMap<String, String>,
+    state: &AppState,
+) -> Result<Json<GraphPath>, ApiServerError> {
+    let from = query.get("from").cloned().unwrap_or_default();
+    let to = query.get("to").cloned().unwrap_or_default();
+    if from.is_empty() || to.is_empty() {
+        error!("from or to is empty");
+        return Err(ApiServerError::InvalidParameter);
+    }
+    let traversal = graph_traversal_from_query(&query, "max_depth", DEFAULT_PATH_DEPTH)?;
+    let registry = state.registry()?;
+    let path = registry
+        .find_graph_path(&from, &to, &traversal)
+        .await
+        .map_err(|e| {
+            error!("failed at find_graph_path; {e}");
+            ApiServerError::MeilisearchError
+        })?
+        .ok_or(ApiServerError::NotFound)?;
+    Ok(Json(path))
+}
+
+async fn v1_get_document(
+    id: String,
+    state: &AppState,
+) -> Result<Json<LegalDocument>, ApiServerError> {
+    let registry = state.registry()?;
+    let document = registry.get_document(&id).await.map_err(|e| {
+        error!("failed at get_document; {e}");
+        ApiServerError::MeilisearchError
+    })?;
+    document.map(Json).ok_or(ApiServerError::NotFound)
+}
+
+async fn v1_get_similar(
+    id: String,
+    options: SearchOptions,
+    state: &AppState,
+) -> Result<Json<V1SearchResponse>, ApiServerError> {
+    let registry = state.search_registry()?;
+    let search_result = registry
+        .find_similar(&id, &options)
+        .await
+        .map_err(|e| {
+            error!("failed at find_similar; {e}");
+            match e {
+                LawscapeCoreError::InvalidParameter(_) => ApiServerError::InvalidParameter,
+                _ => ApiServerError::SearchError,
+            }
+        })?
+        .ok_or(ApiServerError::NotFound)?;
+    let pagination = Pagination {
+        offset: search_result.offset,
+        limit: search_result.limit,
+        hits: search_result.hits.len(),
+        estimated_total_hits: search_result.estimated_total_hits,
+        next_offset: search_result.next_offset(),
+    };
+    Ok(Json(V1SearchResponse {
+        results: V1SearchResults::Flat(search_result.hits),
+        pagination,
+        facets: None,
+    }))
+}
+
+async fn v1_get_law(law_id: String, state: &AppState) -> Result<Json<Vec<Law>>, ApiServerError> {
+    let registry = state.registry()?;
+    let law_articles = registry.get_law_articles(&law_id).await.map_err(|e| {
+        error!("failed at get_law_articles; {e}");
+        ApiServerError::MeilisearchError
+    })?;
+    if law_articles.is_empty() {
+        Err(ApiServerError::NotFound)
+    } else {
+        Ok(Json(law_articles))
+    }
+}
+
+/// 法令の中で定義されている用語を返す
+async fn v1_get_law_definitions(
+    law_id: String,
+    state: &AppState,
+) -> Result<Json<Vec<DefinedTerm>>, ApiServerError> {
+    let registry = state.registry()?;
+    let definitions = registry
+        .get_law_definitions(&law_id)
+        .await
+        .map_err(|e| {
+            error!("failed at get_law_definitions; {e}");
+            ApiServerError::MeilisearchError
+        })?
+        .ok_or(ApiServerError::NotFound)?;
+    Ok(Json(definitions))
+}
+
+/// 用語の定義を全ての法令から探して返す
+async fn v1_find_definitions(
+    query: HashMap<String, String>,
+    state: &AppState,
+) -> Result<Json<Vec<DefinedTerm>>, ApiServerError> {
+    let term = query.get("term").cloned().unwrap_or_default();
+    if term.is_empty() {
+        error!("term is empty");
+        return Err(ApiServerError::InvalidParameter);
+    }
+    let limit = query
+        .get("limit")
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(state.default_limit);
+    let offset = page_to_offset(
+        query.get("page").and_then(|s| s.parse::<usize>().ok()),
+        query.get("offset").and_then(|s| s.parse::<usize>().ok()),
+        limit,
+    );
+    let registry = state.registry()?;
+    let definitions = registry
+        .find_definitions(&term, limit, offset)
+        .await
+        .map_err(|e| {
+            error!("failed at find_definitions; {e}");
+            ApiServerError::MeilisearchError
+        })?;
+    Ok(Json(definitions))
+}
+
+/// 法令から委任している政令や府省令を木の形で返す
+async fn v1_get_delegation_tree(
+    law_id: String,
+    state: &AppState,
+) -> Result<Json<DelegationNode>, ApiServerError> {
+    let registry = state.registry()?;
+    let tree = registry
+        .get_delegation_tree(&law_id)
+        .await
+        .map_err(|e| {
+            error!("failed at get_delegation_tree; {e}");
+            ApiServerError::MeilisearchError
+        })?
+        .ok_or(ApiServerError::NotFound)?;
+    Ok(Json(tree))
+}
+
+async fn v1_get_precedent(id: String, state: &AppState) -> Result<Json<Precedent>, ApiServerError> {
+    let registry = state.registry()?;
+    let precedent = registry.get_precedent(&id).await.map_err(|e| {
+        error!("failed at get_precedent; {e}");
+        ApiServerError::MeilisearchError
+    })?;
+    precedent.map(Json).ok_or(ApiServerError::NotFound)
+}
+
+async fn v1_get_precedents_by_statute(
+    query: HashMap<String, String>,
+    state: &AppState,
+) -> Result<Json<Vec<StatutePrecedent>>, ApiServerError> {
+    let statute = query.get("statute").cloned().unwrap_or_default();
+    if statute.is_empty() {
+        error!("statute is empty");
+        return Err(ApiServerError::InvalidParameter);
+    }
+    let limit = query
+        .get("limit")
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(state.default_limit);
+    let offset = page_to_offset(
+        query.get("page").and_then(|s| s.parse::<usize>().ok()),
+        query.get("offset").and_then(|s| s.parse::<usize>().ok()),
+        limit,
+    );
+    let registry = state.registry()?;
+    let precedents = registry
+        .find_precedents_by_statute(&statute, limit, offset)
+        .await
+        .map_err(|e| {
+            error!("failed at find_precedents_by_statute; {e}");
+            match e {
+                LawscapeCoreError::InvalidParameter(_) => ApiServerError::InvalidParameter,
+                _ => ApiServerError::SearchError,
+            }
+        })?
+        .ok_or(ApiServerError::NotFound)?;
+    Ok(Json(precedents))
+}
+
+async fn v1_get_precedent_sections(
+    id: String,
+    state: &AppState,
+) -> Result<Json<Vec<Precedent>>, ApiServerError> {
+    let registry = state.registry()?;
+    let sections = registry.get_precedent_sections(&id).await.map_err(|e| {
+        error!("failed at get_precedent_sections; {e}");
+        ApiServerError::MeilisearchError
+    })?;
+    if sections.is_empty() {
+        Err(ApiServerError::NotFound)
+    } else {
+        Ok(Json(sections))
+    }
+}
+
+async fn v1_get_precedent_citations(
+    id: String,
+    query: HashMap<String, String>,
+    state: &AppState,
+) -> Result<Json<PrecedentCitations>, ApiServerError> {
+    let limit = query
+        .get("limit")
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(state.default_limit);
+    let offset = page_to_offset(
+        query.get("page").and_then(|s| s.parse::<usize>().ok()),
+        query.get("offset").and_then(|s| s.parse::<usize>().ok()),
+        limit,
+    );
+    let registry = state.registry()?;
+    let citations = registry
+        .get_precedent_citations(&id, limit, offset)
+        .await
+        .map_err(|e| {
+            error!("failed at get_precedent_citations; {e}");
+            ApiServerError::MeilisearchError
+        })?;
+    citations.map(Json).ok_or(ApiServerError::NotFound)
+}
+
+async fn v1_get_resolve(
+    cite: String,
+    state: &AppState,
+) -> Result<Json<CitationResolution>, ApiServerError> {
+    let registry = state.registry()?;
+    let resolution = registry.resolve_citation(&cite).await.map_err(|e| {
+        error!("failed at resolve_citation; {e}");
+        match e {
+            LawscapeCoreError::InvalidParameter(_) => ApiServerError::InvalidParameter,
+            _ => ApiServerError::SearchError,
+        }
+    })?;
+    Ok(Json(resolution))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct V1AnnotateRequest {
+    text: String,
+}
+
+async fn v1_post_annotate(
+    request: V1AnnotateRequest,
+    state: &AppState,
+) -> Result<Json<Vec<Annotation>>, ApiServerError> {
+    let registry = state
+        .registry()?
+        .with_law_names_cache(state.law_names_cache.clone());
+    let annotations = registry.annotate(&request.text).await.map_err(|e| {
+        error!("failed at annotate; {e}");
+        ApiServerError::SearchError
+    })?;
+    Ok(Json(annotations))
+}
+
+/// 入力補完で返す候補の数のデフォルト値
+const DEFAULT_SUGGEST_LIMIT: usize = 10;
+
+async fn v1_get_suggest(
+    query: HashMap<String, String>,
+    state: &AppState,
+) -> Result<Json<Vec<Suggestion>>, ApiServerError> {
+    let prefix = query.get("prefix").cloned().unwrap_or_default();
+    if prefix.is_empty() {
+        error!("suggest prefix is empty");
+        return Err(ApiServerError::InvalidParameter);
+    }
+    let limit = query
+        .get("limit")
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_SUGGEST_LIMIT);
+    let document_type = query
+        .get("type")
+        .map(|s| s.parse::<LegalDocumentType>())
+        .transpose()
+        .map_err(|e| {
+            error!("invalid suggest type; {e}");
+            ApiServerError::InvalidParameter
+        })?;
+    let registry = state.registry()?;
+    let suggestions = registry
+        .suggest(&prefix, document_type, limit)
+        .await
+        .map_err(|e| {
+            error!("failed at suggest; {e}");
+            ApiServerError::SearchError
+        })?;
+    Ok(Json(suggestions))
+}
+
+/// ページ送りのための情報
+#[derive(Debug, Clone, Serialize)]
+struct Pagination {
+    offset: usize,
+    limit: usize,
+    /// このページに含まれる検索結果の件数
+    hits: usize,
+    estimated_total_hits: Option<usize>,
+    /// 次のページを取得するためのoffset
+    next_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+enum V1SearchResults {
+    Grouped(Vec<LegalDocumentDependencies>),
+    Flat(Vec<LegalDocumentSearchResult>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct V1SearchResponse {
+    results: V1SearchResults,
+    pagination: Pagination,
+    /// 属性ごとの値とその件数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    facets: Option<BTreeMap<String, BTreeMap<String, usize>>>,
+}
+
+impl From<Pagination> for PaginationDto {
+    fn from(pagination: Pagination) -> Self {
+        Self {
+            offset: pagination.offset,
+            limit: pagination.limit,
+            hits: pagination.hits,
+            estimated_total_hits: pagination.estimated_total_hits,
+            next_offset: pagination.next_offset,
+        }
+    }
+}
+
+impl From<V1SearchResults> for SearchResultsDto {
+    fn from(results: V1SearchResults) -> Self {
+        match results {
+            V1SearchResults::Grouped(groups) => SearchResultsDto::Grouped {
+                groups: groups.iter().map(SearchGroupDto::from).collect(),
+            },
+            V1SearchResults::Flat(hits) => SearchResultsDto::Flat {
+                hits: hits.iter().map(SearchHitDto::from).collect(),
+            },
+        }
+    }
+}
+
+/// v1と同じ条件で検索し、v2の形で返す
+async fn v2_search(
+    search_params: SearchParams,
+    state: &AppState,
+) -> Result<Json<Envelope<SearchResultsDto>>, V2Error> {
+    let Json(response) = v1_search(search_params, state).await?;
+    Ok(Json(
+        Envelope::new(response.results.into())
+            .with_pagination(response.pagination.into())
+            .with_facets(response.facets),
+    ))
+}
+
+async fn v2_get_document(
+    id: String,
+    state: &AppState,
+) -> Result<Json<Envelope<DocumentDto>>, V2Error> {
+    let Json(document) = v1_get_document(id, state).await?;
+    Ok(Json(Envelope::new((&document).into())))
+}
+
+async fn v2_get_law(
+    law_id: String,
+    state: &AppState,
+) -> Result<Json<Envelope<Vec<LawArticleDto>>>, V2Error> {
+    let Json(law_articles) = v1_get_law(law_id, state).await?;
+    Ok(Json(Envelope::new(
+        law_articles.iter().map(LawArticleDto::from).collect(),
+    )))
+}
+
+async fn v2_get_precedent(
+    id: String,
+    state: &AppState,
+) -> Result<Json<Envelope<PrecedentDto>>, V2Error> {
+    let Json(precedent) = v1_get_precedent(id, state).await?;
+    Ok(Json(Envelope::new((&precedent).into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_takes_precedence_over_offset() {
+        assert_eq!(page_to_offset(Some(3), Some(5), 20), 40);
+        assert_eq!(page_to_offset(Some(1), None, 20), 0);
+        assert_eq!(page_to_offset(None, Some(5), 20), 5);
+        assert_eq!(page_to_offset(None, None, 20), 0);
+    }
+
+    #[test]
+    fn page_zero_is_first_page() {
+        assert_eq!(page_to_offset(Some(0), Some(5), 20), 0);
+    }
+
+    fn state() -> AppState {
+        AppState {
+            meilisearch_url: "http://localhost:7700".to_string(),
+            meilisearch_master_key: String::new(),
+            default_limit: 20,
+            default_search_cancel_score: 0.0,
+            embedder: None,
+            law_names_cache: LawNamesCache::default(),
+        }
+    }
+
+    fn query(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn invalid_sort_and_order_are_rejected() {
+        let state = state();
+        let params = SearchParams::from_query(
+            &query(&[
+                ("word", "a"),
+                ("sort", "court_rank:desc,date"),
+                ("order", "date"),
+            ]),
+            &state,
+        )
+        .unwrap();
+        assert_eq!(
+            params
+                .options
+                .sort
+                .into_iter()
+                .map(String::from)
+                .collect::<Vec<_>>(),
+            vec!["court_rank:desc", "date:desc"]
+        );
+        assert_eq!(params.order, DependenciesOrder::Date);
+        for (key, value) in [("sort", "dat:desc"), ("sort", "date:up"), ("order", "dat")] {
+            assert!(matches!(
+                SearchParams::from_query(&query(&[("word", "a"), (key, value)]), &state),
+                Err(ApiServerError::InvalidParameter)
+            ));
+        }
+    }
+
+    #[test]
+    fn parse_calendar_dates() {
+        assert_eq!(parse_date_number("2020-04-01"), Some(20200401));
+        assert_eq!(parse_date_number("2020-02-29"), Some(20200229));
+        assert_eq!(parse_date_number("2021-02-29"), None);
+        assert_eq!(parse_date_number("2020-02-31"), None);
+        assert_eq!(parse_date_number("2021-04-31"), None);
+        assert_eq!(parse_date_number("2020-13-01"), None);
+        assert_eq!(parse_date_number("2020-00-10"), None);
+        assert_eq!(parse_date_number("2020-04"), None);
+        assert_eq!(parse_date_number("令和2年"), None);
+    }
+
+    #[test]
+    fn invalid_as_of_is_rejected() {
+        let state = state();
+        let params =
+            SearchParams::from_query(&query(&[("word", "a"), ("as_of", "2020-04-01")]), &state)
+                .unwrap();
+        assert_eq!(params.options.filter.as_of, Some(20200401));
+        assert!(matches!(
+            SearchParams::from_query(&query(&[("word", "a"), ("as_of", "2021-04-31")]), &state),
+            Err(ApiServerError::InvalidParameter)
+        ));
     }
 }
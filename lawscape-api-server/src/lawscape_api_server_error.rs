@@ -16,10 +16,23 @@ pub enum ApiServerError {
     MeilisearchError,
     #[error("search error")]
     SearchError,
+    #[error("document not found")]
+    NotFound,
+    #[error("invalid parameter")]
+    InvalidParameter,
+    #[error("query syntax error; {0}")]
+    QuerySyntax(String),
 }
 
 impl IntoResponse for ApiServerError {
     fn into_response(self) -> Response {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("{self}")).into_response()
+        let status_code = match self {
+            ApiServerError::NotFound => StatusCode::NOT_FOUND,
+            ApiServerError::InvalidParameter | ApiServerError::QuerySyntax(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status_code, format!("{self}")).into_response()
     }
 }
@@ -6,6 +6,7 @@ mod lawscape_api_server_error;
 use lawscape_api_server_error::ApiServerError;
 
 mod app;
+mod v2;
 
 #[derive(Debug, Parser)]
 struct AppArg {
@@ -27,6 +28,9 @@ struct AppArg {
     /// 探索を打ち切る閾値
     #[arg(long)]
     pub search_cancel_score: f64,
+    /// 検索語を埋め込みベクトルに変換し、ハイブリッド検索ができるようにする
+    #[arg(long)]
+    pub embedding: bool,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -49,6 +53,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             app_args.meilisearch_master_key,
             app_args.limit,
             app_args.search_cancel_score,
+            app_args.embedding,
         ))
         .map_err(|_| ApiServerError::TokioRuntime)?;
     Ok(())
@@ -0,0 +1,867 @@
+//! v2のAPIのリクエストとレスポンスの型。
+//! lawscape-coreやjplaw_data_typesの型をそのまま受け取ったり返したりせず、ここで定めた形に変換する。
+//! フィールドの削除や名前の変更はv2の互換性を壊すので、新しいバージョンのAPIで行う。
+
+use crate::lawscape_api_server_error::ApiServerError;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use lawscape_core::law_metadata::LawType;
+use lawscape_core::precedent::PrecedentSectionKind;
+use lawscape_core::{
+    DependenciesOrder, HighlightOptions, Law, LawStatus, LegalDocument, LegalDocumentDependencies,
+    LegalDocumentHighlight, LegalDocumentSearchResult, LegalDocumentType, Precedent, SearchFilter,
+    SearchSort, SortDirection, SortKey,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// レスポンスの`meta.api_version`の値
+const API_VERSION: &str = "2";
+
+/// 全てのレスポンスを包む型
+#[derive(Debug, Clone, Serialize)]
+pub struct Envelope<T> {
+    pub data: T,
+    pub meta: Meta,
+}
+
+impl<T> Envelope<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            data,
+            meta: Meta::default(),
+        }
+    }
+
+    pub fn with_pagination(mut self, pagination: PaginationDto) -> Self {
+        self.meta.pagination = Some(pagination);
+        self
+    }
+
+    pub fn with_facets(
+        mut self,
+        facets: Option<BTreeMap<String, BTreeMap<String, usize>>>,
+    ) -> Self {
+        self.meta.facets = facets;
+        self
+    }
+}
+
+/// レスポンスの付加情報
+#[derive(Debug, Clone, Serialize)]
+pub struct Meta {
+    pub api_version: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<PaginationDto>,
+    /// 属性ごとの値とその件数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<BTreeMap<String, BTreeMap<String, usize>>>,
+}
+
+impl Default for Meta {
+    fn default() -> Self {
+        Self {
+            api_version: API_VERSION,
+            pagination: None,
+            facets: None,
+        }
+    }
+}
+
+/// ページ送りのための情報
+#[derive(Debug, Clone, Serialize)]
+pub struct PaginationDto {
+    pub offset: usize,
+    pub limit: usize,
+    /// このページに含まれる検索結果の件数
+    pub hits: usize,
+    pub estimated_total_hits: Option<usize>,
+    /// 次のページを取得するためのoffset
+    pub next_offset: Option<usize>,
+}
+
+/// エラーのレスポンス。`{"error": {"code": ..., "message": ...}}`の形で返す
+#[derive(Debug, Clone)]
+pub struct V2Error(pub ApiServerError);
+
+impl From<ApiServerError> for V2Error {
+    fn from(e: ApiServerError) -> Self {
+        Self(e)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ErrorBody {
+    error: ErrorDetail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ErrorDetail {
+    code: &'static str,
+    message: String,
+}
+
+impl V2Error {
+    /// ステータスコードとレスポンスの本文を求める
+    fn status_and_body(&self) -> (StatusCode, ErrorBody) {
+        let (status_code, code) = match self.0 {
+            ApiServerError::NotFound => (StatusCode::NOT_FOUND, "not_found"),
+            ApiServerError::InvalidParameter => (StatusCode::BAD_REQUEST, "invalid_parameter"),
+            ApiServerError::QuerySyntax(_) => (StatusCode::BAD_REQUEST, "query_syntax"),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+        };
+        let body = ErrorBody {
+            error: ErrorDetail {
+                code,
+                message: format!("{}", self.0),
+            },
+        };
+        (status_code, body)
+    }
+}
+
+impl IntoResponse for V2Error {
+    fn into_response(self) -> Response {
+        let (status_code, body) = self.status_and_body();
+        (status_code, Json(body)).into_response()
+    }
+}
+
+/// 西暦の年月日を`yyyy-mm-dd`の形の文字列にする。`lawscape_core::date_to_ad`の結果を渡す
+fn format_date((y, m, d): (usize, usize, usize)) -> String {
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+fn law_type_name(law_type: LawType) -> &'static str {
+    match law_type {
+        LawType::Constitution => "constitution",
+        LawType::Act => "act",
+        LawType::CabinetOrder => "cabinet_order",
+        LawType::ImperialOrder => "imperial_order",
+        LawType::MinisterialOrdinance => "ministerial_ordinance",
+        LawType::Rule => "rule",
+        LawType::Misc => "misc",
+    }
+}
+
+fn law_status_name(status: LawStatus) -> &'static str {
+    match status {
+        LawStatus::InForce => "in_force",
+        LawStatus::Repealed => "repealed",
+        LawStatus::NotYetEffective => "not_yet_effective",
+    }
+}
+
+fn section_kind_name(kind: PrecedentSectionKind) -> &'static str {
+    match kind {
+        PrecedentSectionKind::Preamble => "preamble",
+        PrecedentSectionKind::MainText => "main_text",
+        PrecedentSectionKind::FactsAndReasons => "facts_and_reasons",
+        PrecedentSectionKind::Facts => "facts",
+        PrecedentSectionKind::Reasons => "reasons",
+        PrecedentSectionKind::SupplementaryOpinion => "supplementary_opinion",
+        PrecedentSectionKind::DissentingOpinion => "dissenting_opinion",
+        PrecedentSectionKind::Opinion => "opinion",
+    }
+}
+
+/// 法令の条文
+#[derive(Debug, Clone, Serialize)]
+pub struct LawArticleDto {
+    /// 文書のID
+    pub id: String,
+    pub law_id: String,
+    pub law_name: String,
+    /// 法令番号
+    pub law_num: Option<String>,
+    /// `constitution`・`act`・`cabinet_order`・`imperial_order`・`ministerial_ordinance`・`rule`・`misc`
+    pub law_type: Option<&'static str>,
+    /// `in_force`・`repealed`・`not_yet_effective`
+    pub status: &'static str,
+    /// 条番号。「第三条の二」は`3_2`
+    pub article: String,
+    /// 項ごとに分けて登録した場合の項番号
+    pub paragraph: Option<String>,
+    /// 条文の見出し
+    pub caption: Option<String>,
+    /// 編・章・節・款・目の見出しを外側から順に並べたもの
+    pub chapter_path: Vec<String>,
+    /// 附則の条文の場合は附則の見出し
+    pub supplementary_provision: Option<String>,
+    pub text: String,
+    /// 法令内での条文の出現順
+    pub position: usize,
+    /// 公布日。`yyyy-mm-dd`
+    pub promulgation_date: Option<String>,
+    /// この版の施行日。`yyyy-mm-dd`
+    pub effective_date: Option<String>,
+}
+
+impl From<&Law> for LawArticleDto {
+    fn from(law: &Law) -> Self {
+        Self {
+            id: law.id.clone(),
+            law_id: law.law_id.to_string(),
+            law_name: law.name.clone(),
+            law_num: law.law_num.clone(),
+            law_type: law.law_type.map(law_type_name),
+            status: law_status_name(law.status),
+            article: law.index.article.clone(),
+            paragraph: law.paragraph.clone(),
+            caption: law.caption.clone(),
+            chapter_path: law.chapter_path.clone(),
+            supplementary_provision: law.suppl_provision.clone(),
+            text: law.text.clone(),
+            position: law.position,
+            promulgation_date: law
+                .promulgation_date
+                .as_ref()
+                .map(|date| format_date(lawscape_core::date_to_ad(date))),
+            effective_date: law
+                .effective_date
+                .as_ref()
+                .map(|date| format_date(lawscape_core::date_to_ad(date))),
+        }
+    }
+}
+
+/// 判例、または判例の判決文の区分
+#[derive(Debug, Clone, Serialize)]
+pub struct PrecedentDto {
+    /// 文書のID
+    pub id: String,
+    /// 判決全体のID
+    pub precedent_id: String,
+    pub case_name: String,
+    pub case_number: String,
+    pub lawsuit_id: String,
+    pub court_name: String,
+    /// 判決日。`yyyy-mm-dd`
+    pub date: String,
+    /// 判決文の区分。区分ごとに分けて登録していない場合は`None`
+    pub section: Option<PrecedentSectionDto>,
+    pub text: String,
+}
+
+/// 判決文の区分
+#[derive(Debug, Clone, Serialize)]
+pub struct PrecedentSectionDto {
+    /// `preamble`・`main_text`・`facts_and_reasons`・`facts`・`reasons`・
+    /// `supplementary_opinion`・`dissenting_opinion`・`opinion`
+    pub kind: &'static str,
+    pub title: String,
+    /// 判決文の中での区分の出現順
+    pub position: usize,
+}
+
+impl From<&Precedent> for PrecedentDto {
+    fn from(precedent: &Precedent) -> Self {
+        Self {
+            id: precedent.id.clone(),
+            precedent_id: precedent.precedent_id().to_string(),
+            case_name: precedent.info.case_name.clone(),
+            case_number: precedent.info.case_number.clone(),
+            lawsuit_id: precedent.info.lawsuit_id.clone(),
+            court_name: precedent.info.court_name.clone(),
+            date: format_date(lawscape_core::date_to_ad(&precedent.info.date)),
+            section: precedent
+                .section
+                .as_ref()
+                .map(|section| PrecedentSectionDto {
+                    kind: section_kind_name(section.kind),
+                    title: section.title.clone(),
+                    position: section.position,
+                }),
+            text: precedent.text.clone(),
+        }
+    }
+}
+
+/// 文書。`type`で法令の条文（`law`）と判例（`precedent`）を区別する
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DocumentDto {
+    Law(LawArticleDto),
+    Precedent(PrecedentDto),
+}
+
+impl From<&LegalDocument> for DocumentDto {
+    fn from(document: &LegalDocument) -> Self {
+        match document {
+            LegalDocument::Law(l) => DocumentDto::Law(l.into()),
+            LegalDocument::Precedent(p) => DocumentDto::Precedent(p.into()),
+        }
+    }
+}
+
+/// 本文中のマッチ箇所。位置と長さはバイト単位
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchPositionDto {
+    pub start: usize,
+    pub length: usize,
+}
+
+/// 検索結果のハイライト情報
+#[derive(Debug, Clone, Serialize)]
+pub struct HighlightDto {
+    pub snippet: Option<String>,
+    pub matches: Vec<MatchPositionDto>,
+}
+
+impl From<&LegalDocumentHighlight> for HighlightDto {
+    fn from(highlight: &LegalDocumentHighlight) -> Self {
+        Self {
+            snippet: highlight.snippet.clone(),
+            matches: highlight
+                .matches
+                .iter()
+                .map(|m| MatchPositionDto {
+                    start: m.start,
+                    length: m.length,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 検索結果の一件
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHitDto {
+    pub score: Option<f64>,
+    pub document: DocumentDto,
+    pub highlight: Option<HighlightDto>,
+}
+
+impl From<&LegalDocumentSearchResult> for SearchHitDto {
+    fn from(result: &LegalDocumentSearchResult) -> Self {
+        Self {
+            score: result.score,
+            document: (&result.document).into(),
+            highlight: result.highlight.as_ref().map(HighlightDto::from),
+        }
+    }
+}
+
+/// 法令または判例ごとにまとめた検索結果
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchGroupDto {
+    /// 法令IDまたは判決全体のID
+    pub group_id: String,
+    pub hits: Vec<SearchHitDto>,
+    /// 参照している法令のグループID
+    pub references: Vec<String>,
+    /// 参照されている法令のグループID
+    pub referenced_by: Vec<String>,
+    /// `references`のうち委任元にあたるもの
+    pub delegated_from: Vec<String>,
+    /// `referenced_by`のうち委任先にあたるもの
+    pub delegated_to: Vec<String>,
+}
+
+impl From<&LegalDocumentDependencies> for SearchGroupDto {
+    fn from(dependencies: &LegalDocumentDependencies) -> Self {
+        Self {
+            group_id: dependencies
+                .contents
+                .first()
+                .map(|c| c.document.get_group_id())
+                .unwrap_or_default(),
+            hits: dependencies
+                .contents
+                .iter()
+                .map(SearchHitDto::from)
+                .collect(),
+            references: dependencies.parents.clone(),
+            referenced_by: dependencies.children.clone(),
+            delegated_from: dependencies.delegating.clone(),
+            delegated_to: dependencies.delegated.clone(),
+        }
+    }
+}
+
+/// 検索結果。`shape`でまとめ方（`grouped`・`flat`）を区別する
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum SearchResultsDto {
+    Grouped { groups: Vec<SearchGroupDto> },
+    Flat { hits: Vec<SearchHitDto> },
+}
+
+/// POST /v2/searchで受け取る検索条件
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchRequestDto {
+    pub query: String,
+    /// `query`を検索式として解析するかどうか
+    #[serde(default)]
+    pub query_syntax: bool,
+    /// 「民法709条」のような引用。指定した条文を参照している判例に絞り込む
+    pub statute: Option<String>,
+    #[serde(default)]
+    pub filter: SearchFilterDto,
+    /// 並び替えの条件。先頭のものから優先する。空の場合はスコアの順
+    #[serde(default)]
+    pub sort: Vec<SearchSortDto>,
+    /// 法令または判例ごとにまとめた検索結果の並び順
+    #[serde(default)]
+    pub group_order: GroupOrderDto,
+    #[serde(default)]
+    pub shape: ResultShapeDto,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    pub cancel_score: Option<f64>,
+    /// 与えると本文のマッチ箇所の情報も返す
+    pub highlight: Option<HighlightRequestDto>,
+    /// 属性ごとの値とその件数を`meta.facets`に返すかどうか
+    #[serde(default)]
+    pub facets: bool,
+    /// 0に近いほどキーワード検索を、1に近いほどベクトル検索を重視する
+    pub semantic_ratio: Option<f32>,
+}
+
+/// 検索結果の絞り込み条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchFilterDto {
+    /// `law`・`precedent`
+    pub document_type: Option<DocumentTypeDto>,
+    pub law_ids: Vec<String>,
+    pub court_names: Vec<String>,
+    /// 判例の判決日の西暦での年
+    pub years: Vec<usize>,
+    pub sections: Vec<SectionKindDto>,
+    pub law_types: Vec<LawTypeDto>,
+    pub include_repealed: bool,
+    pub include_future: bool,
+    /// `yyyy-mm-dd`の形の日付。この日の時点の文書に絞り込む
+    #[serde(deserialize_with = "crate::app::deserialize_date_number")]
+    pub as_of: Option<usize>,
+}
+
+impl From<SearchFilterDto> for SearchFilter {
+    fn from(filter: SearchFilterDto) -> Self {
+        SearchFilter {
+            document_type: filter.document_type.map(|t| match t {
+                DocumentTypeDto::Law => LegalDocumentType::Law,
+                DocumentTypeDto::Precedent => LegalDocumentType::Precedent,
+            }),
+            law_ids: filter.law_ids,
+            court_names: filter.court_names,
+            years: filter.years,
+            sections: filter.sections.into_iter().map(|s| s.into()).collect(),
+            law_types: filter.law_types.into_iter().map(|t| t.into()).collect(),
+            statutes: Vec::new(),
+            include_repealed: filter.include_repealed,
+            include_future: filter.include_future,
+            as_of: filter.as_of,
+        }
+    }
+}
+
+/// 文書の種類
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentTypeDto {
+    Law,
+    Precedent,
+}
+
+/// 判決文の区分の種類
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SectionKindDto {
+    Preamble,
+    MainText,
+    FactsAndReasons,
+    Facts,
+    Reasons,
+    SupplementaryOpinion,
+    DissentingOpinion,
+    Opinion,
+}
+
+impl From<SectionKindDto> for PrecedentSectionKind {
+    fn from(kind: SectionKindDto) -> Self {
+        match kind {
+            SectionKindDto::Preamble => PrecedentSectionKind::Preamble,
+            SectionKindDto::MainText => PrecedentSectionKind::MainText,
+            SectionKindDto::FactsAndReasons => PrecedentSectionKind::FactsAndReasons,
+            SectionKindDto::Facts => PrecedentSectionKind::Facts,
+            SectionKindDto::Reasons => PrecedentSectionKind::Reasons,
+            SectionKindDto::SupplementaryOpinion => PrecedentSectionKind::SupplementaryOpinion,
+            SectionKindDto::DissentingOpinion => PrecedentSectionKind::DissentingOpinion,
+            SectionKindDto::Opinion => PrecedentSectionKind::Opinion,
+        }
+    }
+}
+
+/// 法令の種類
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LawTypeDto {
+    Constitution,
+    Act,
+    CabinetOrder,
+    ImperialOrder,
+    MinisterialOrdinance,
+    Rule,
+    Misc,
+}
+
+impl From<LawTypeDto> for LawType {
+    fn from(law_type: LawTypeDto) -> Self {
+        match law_type {
+            LawTypeDto::Constitution => LawType::Constitution,
+            LawTypeDto::Act => LawType::Act,
+            LawTypeDto::CabinetOrder => LawType::CabinetOrder,
+            LawTypeDto::ImperialOrder => LawType::ImperialOrder,
+            LawTypeDto::MinisterialOrdinance => LawType::MinisterialOrdinance,
+            LawTypeDto::Rule => LawType::Rule,
+            LawTypeDto::Misc => LawType::Misc,
+        }
+    }
+}
+
+/// 並び替えの条件。`{"key": "date", "direction": "desc"}`の形で受け取る
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchSortDto {
+    /// `date`・`court_rank`
+    pub key: SortKeyDto,
+    /// `asc`・`desc`。省略した場合は`desc`
+    #[serde(default)]
+    pub direction: SortDirectionDto,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKeyDto {
+    Date,
+    CourtRank,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirectionDto {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl From<SearchSortDto> for SearchSort {
+    fn from(sort: SearchSortDto) -> Self {
+        SearchSort {
+            key: match sort.key {
+                SortKeyDto::Date => SortKey::Date,
+                SortKeyDto::CourtRank => SortKey::CourtRank,
+            },
+            direction: match sort.direction {
+                SortDirectionDto::Asc => SortDirection::Asc,
+                SortDirectionDto::Desc => SortDirection::Desc,
+            },
+        }
+    }
+}
+
+/// 法令または判例ごとにまとめた検索結果の並び順
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupOrderDto {
+    /// まとまりの中で最も高いスコアの降順
+    #[default]
+    Score,
+    /// 法令IDの順
+    LawId,
+    /// 判例の日付の新しい順
+    Date,
+    /// 判例の裁判所の審級の高い順
+    CourtRank,
+}
+
+impl From<GroupOrderDto> for DependenciesOrder {
+    fn from(order: GroupOrderDto) -> Self {
+        match order {
+            GroupOrderDto::Score => DependenciesOrder::Score,
+            GroupOrderDto::LawId => DependenciesOrder::LawId,
+            GroupOrderDto::Date => DependenciesOrder::Date,
+            GroupOrderDto::CourtRank => DependenciesOrder::CourtRank,
+        }
+    }
+}
+
+/// 検索結果のまとめ方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultShapeDto {
+    #[default]
+    Grouped,
+    Flat,
+}
+
+/// 検索結果のハイライトの設定。省略したフィールドは既定値になる
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HighlightRequestDto {
+    pub pre_tag: Option<String>,
+    pub post_tag: Option<String>,
+    /// マッチ箇所の周辺を切り出す際の語数。省略した場合は本文全体
+    pub crop_length: Option<usize>,
+    pub crop_marker: Option<String>,
+}
+
+impl From<HighlightRequestDto> for HighlightOptions {
+    fn from(highlight: HighlightRequestDto) -> Self {
+        let default = HighlightOptions::default();
+        HighlightOptions {
+            pre_tag: highlight.pre_tag.unwrap_or(default.pre_tag),
+            post_tag: highlight.post_tag.unwrap_or(default.post_tag),
+            crop_length: highlight.crop_length,
+            crop_marker: highlight.crop_marker.unwrap_or(default.crop_marker),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn law_article() -> LawArticleDto {
+        LawArticleDto {
+            id: "law_1".to_string(),
+            law_id: "129AC0000000089".to_string(),
+            law_name: "民法".to_string(),
+            law_num: Some("明治二十九年法律第八十九号".to_string()),
+            law_type: Some("act"),
+            status: "in_force",
+            article: "709".to_string(),
+            paragraph: None,
+            caption: Some("（不法行為による損害賠償）".to_string()),
+            chapter_path: vec!["第三編　債権".to_string()],
+            supplementary_provision: None,
+            text: "故意又は過失によって…".to_string(),
+            position: 1,
+            promulgation_date: Some("1896-04-27".to_string()),
+            effective_date: None,
+        }
+    }
+
+    fn hit() -> SearchHitDto {
+        SearchHitDto {
+            score: Some(0.5),
+            document: DocumentDto::Law(law_article()),
+            highlight: Some(HighlightDto {
+                snippet: Some("<em>過失</em>".to_string()),
+                matches: vec![MatchPositionDto {
+                    start: 3,
+                    length: 6,
+                }],
+            }),
+        }
+    }
+
+    #[test]
+    fn envelope_json() {
+        let envelope = Envelope::new(vec![1, 2]).with_pagination(PaginationDto {
+            offset: 0,
+            limit: 2,
+            hits: 2,
+            estimated_total_hits: Some(5),
+            next_offset: Some(2),
+        });
+        assert_eq!(
+            serde_json::to_value(&envelope).unwrap(),
+            json!({
+                "data": [1, 2],
+                "meta": {
+                    "api_version": "2",
+                    "pagination": {
+                        "offset": 0,
+                        "limit": 2,
+                        "hits": 2,
+                        "estimated_total_hits": 5,
+                        "next_offset": 2
+                    }
+                }
+            })
+        );
+        let facets =
+            BTreeMap::from([("type".to_string(), BTreeMap::from([("Law".to_string(), 3)]))]);
+        assert_eq!(
+            serde_json::to_value(Envelope::new("x").with_facets(Some(facets))).unwrap(),
+            json!({
+                "data": "x",
+                "meta": {"api_version": "2", "facets": {"type": {"Law": 3}}}
+            })
+        );
+    }
+
+    #[test]
+    fn document_json() {
+        assert_eq!(
+            serde_json::to_value(DocumentDto::Law(law_article())).unwrap(),
+            json!({
+                "type": "law",
+                "id": "law_1",
+                "law_id": "129AC0000000089",
+                "law_name": "民法",
+                "law_num": "明治二十九年法律第八十九号",
+                "law_type": "act",
+                "status": "in_force",
+                "article": "709",
+                "paragraph": null,
+                "caption": "（不法行為による損害賠償）",
+                "chapter_path": ["第三編　債権"],
+                "supplementary_provision": null,
+                "text": "故意又は過失によって…",
+                "position": 1,
+                "promulgation_date": "1896-04-27",
+                "effective_date": null
+            })
+        );
+        let precedent = PrecedentDto {
+            id: "precedent_1_2".to_string(),
+            precedent_id: "1".to_string(),
+            case_name: "損害賠償請求事件".to_string(),
+            case_number: "昭和48(オ)1".to_string(),
+            lawsuit_id: "1".to_string(),
+            court_name: "最高裁判所第一小法廷".to_string(),
+            date: "1973-04-05".to_string(),
+            section: Some(PrecedentSectionDto {
+                kind: "main_text",
+                title: "主文".to_string(),
+                position: 2,
+            }),
+            text: "本件上告を棄却する。".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(DocumentDto::Precedent(precedent)).unwrap(),
+            json!({
+                "type": "precedent",
+                "id": "precedent_1_2",
+                "precedent_id": "1",
+                "case_name": "損害賠償請求事件",
+                "case_number": "昭和48(オ)1",
+                "lawsuit_id": "1",
+                "court_name": "最高裁判所第一小法廷",
+                "date": "1973-04-05",
+                "section": {"kind": "main_text", "title": "主文", "position": 2},
+                "text": "本件上告を棄却する。"
+            })
+        );
+    }
+
+    #[test]
+    fn search_results_json() {
+        let grouped = SearchResultsDto::Grouped {
+            groups: vec![SearchGroupDto {
+                group_id: "129AC0000000089".to_string(),
+                hits: vec![hit()],
+                references: vec!["a".to_string()],
+                referenced_by: vec!["b".to_string()],
+                delegated_from: Vec::new(),
+                delegated_to: vec!["b".to_string()],
+            }],
+        };
+        let value = serde_json::to_value(grouped).unwrap();
+        assert_eq!(value["shape"], "grouped");
+        let group = &value["groups"][0];
+        assert_eq!(group["group_id"], "129AC0000000089");
+        assert_eq!(group["references"], json!(["a"]));
+        assert_eq!(group["referenced_by"], json!(["b"]));
+        assert_eq!(group["delegated_from"], json!([]));
+        assert_eq!(group["delegated_to"], json!(["b"]));
+        assert_eq!(group["hits"][0]["score"], 0.5);
+        assert_eq!(group["hits"][0]["document"]["type"], "law");
+        assert_eq!(
+            group["hits"][0]["highlight"],
+            json!({"snippet": "<em>過失</em>", "matches": [{"start": 3, "length": 6}]})
+        );
+
+        let flat = SearchResultsDto::Flat { hits: Vec::new() };
+        assert_eq!(
+            serde_json::to_value(flat).unwrap(),
+            json!({"shape": "flat", "hits": []})
+        );
+    }
+
+    #[test]
+    fn error_json() {
+        let (status_code, body) = V2Error(ApiServerError::NotFound).status_and_body();
+        assert_eq!(status_code, StatusCode::NOT_FOUND);
+        assert_eq!(
+            serde_json::to_value(body).unwrap(),
+            json!({"error": {"code": "not_found", "message": "document not found"}})
+        );
+        let (status_code, body) =
+            V2Error(ApiServerError::QuerySyntax("unclosed quote".to_string())).status_and_body();
+        assert_eq!(status_code, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            serde_json::to_value(body).unwrap(),
+            json!({
+                "error": {
+                    "code": "query_syntax",
+                    "message": "query syntax error; unclosed quote"
+                }
+            })
+        );
+        let (status_code, body) = V2Error(ApiServerError::MeilisearchError).status_and_body();
+        assert_eq!(status_code, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            serde_json::to_value(body).unwrap()["error"]["code"],
+            "internal_error"
+        );
+    }
+
+    #[test]
+    fn search_request_from_json() {
+        let request: SearchRequestDto = serde_json::from_value(json!({
+            "query": "不法行為",
+            "filter": {
+                "document_type": "precedent",
+                "sections": ["main_text"],
+                "law_types": ["cabinet_order"],
+                "as_of": "2020-04-01"
+            },
+            "sort": [{"key": "court_rank"}, {"key": "date", "direction": "asc"}],
+            "group_order": "law_id",
+            "shape": "flat",
+            "highlight": {"crop_length": 10}
+        }))
+        .unwrap();
+        assert_eq!(request.shape, ResultShapeDto::Flat);
+        assert_eq!(
+            DependenciesOrder::from(request.group_order),
+            DependenciesOrder::LawId
+        );
+        let sort = request
+            .sort
+            .into_iter()
+            .map(|s| String::from(SearchSort::from(s)))
+            .collect::<Vec<_>>();
+        assert_eq!(sort, vec!["court_rank:desc", "date:asc"]);
+        let highlight = HighlightOptions::from(request.highlight.unwrap());
+        assert_eq!(highlight.pre_tag, "<em>");
+        assert_eq!(highlight.crop_length, Some(10));
+        let filter = SearchFilter::from(request.filter);
+        assert_eq!(filter.document_type, Some(LegalDocumentType::Precedent));
+        assert_eq!(filter.sections, vec![PrecedentSectionKind::MainText]);
+        assert_eq!(filter.law_types, vec![LawType::CabinetOrder]);
+        assert_eq!(filter.as_of, Some(20200401));
+        assert!(!filter.include_repealed);
+
+        assert!(
+            serde_json::from_value::<SearchRequestDto>(json!({"query": "a", "order": "date"}))
+                .is_err()
+        );
+        assert!(
+            serde_json::from_value::<SearchRequestDto>(json!({
+                "query": "a",
+                "filter": {"as_of": "2020-13-01"}
+            }))
+            .is_err()
+        );
+    }
+}
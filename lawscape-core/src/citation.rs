@@ -0,0 +1,979 @@
+//! 「民法709条」のような法令の引用を解析する
+
+use crate::LawscapeCoreError;
+use jplaw_data_types::law::Era;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// よく使われる法令の略称と正式名称の対応
+const LAW_NAME_ABBREVIATIONS: &[(&str, &str)] = &[
+    ("憲法", "日本国憲法"),
+    ("民訴法", "民事訴訟法"),
+    ("民訴", "民事訴訟法"),
+    ("刑訴法", "刑事訴訟法"),
+    ("刑訴", "刑事訴訟法"),
+    ("民執法", "民事執行法"),
+    ("民保法", "民事保全法"),
+    ("行訴法", "行政事件訴訟法"),
+    ("行手法", "行政手続法"),
+    ("国賠法", "国家賠償法"),
+    ("労基法", "労働基準法"),
+    ("労契法", "労働契約法"),
+    ("労組法", "労働組合法"),
+    ("借地借家", "借地借家法"),
+    ("独禁法", "私的独占の禁止及び公正取引の確保に関する法律"),
+    ("個人情報保護法", "個人情報の保護に関する法律"),
+    ("金商法", "金融商品取引法"),
+    ("景表法", "不当景品類及び不当表示防止法"),
+    ("特商法", "特定商取引に関する法律"),
+    ("消契法", "消費者契約法"),
+    ("破産", "破産法"),
+    ("民再法", "民事再生法"),
+    ("会更法", "会社更生法"),
+    ("著作権", "著作権法"),
+    ("特許", "特許法"),
+];
+
+/// 条番号などに使われる数の正規表現
+const NUMBER_PATTERN: &str = "[0-9０-９〇零一二三四五六七八九十百千万]+";
+
+/// 直後に条番号が続くかどうかを判定する正規表現
+static ARTICLE_FOLLOWING_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!("^第?{NUMBER_PATTERN}条")).unwrap());
+
+/// 法令の引用を解析した結果
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Citation {
+    /// 引用に書かれていた法令名
+    pub law_name: String,
+    /// 略称を展開した法令名
+    pub official_law_name: String,
+    /// 条番号。「第三条の二」は`3_2`のように表す
+    pub article: Option<String>,
+    /// 項番号
+    pub paragraph: Option<usize>,
+    /// 号番号
+    pub item: Option<usize>,
+}
+
+/// 漢数字・全角数字・半角数字で書かれた数を解析する
+pub fn parse_japanese_number(str: &str) -> Option<usize> {
+    if str.is_empty() {
+        return None;
+    }
+    let normalized = normalize_digits(str);
+    if normalized.chars().all(|c| c.is_ascii_digit()) {
+        return normalized.parse::<usize>().ok();
+    }
+    let mut total = 0;
+    // 万の位より下の部分
+    let mut section = 0;
+    // 直前に現れた数字
+    let mut digit: Option<usize> = None;
+    for c in normalized.chars() {
+        let unit = match c {
+            '十' => 10,
+            '百' => 100,
+            '千' => 1000,
+            '万' => 10000,
+            _ => {
+                let d = kanji_digit(c)?;
+                digit = Some(digit.unwrap_or(0) * 10 + d);
+                continue;
+            }
+        };
+        if unit == 10000 {
+            section += digit.unwrap_or(0);
+            total += section.max(1) * unit;
+            section = 0;
+        } else {
+            section += digit.unwrap_or(1) * unit;
+        }
+        digit = None;
+    }
+    Some(total + section + digit.unwrap_or(0))
+}
+
+fn kanji_digit(c: char) -> Option<usize> {
+    match c {
+        '〇' | '零' => Some(0),
+        '一' => Some(1),
+        '二' => Some(2),
+        '三' => Some(3),
+        '四' => Some(4),
+        '五' => Some(5),
+        '六' => Some(6),
+        '七' => Some(7),
+        '八' => Some(8),
+        '九' => Some(9),
+        _ => c.to_digit(10).map(|d| d as usize),
+    }
+}
+
+/// 全角数字を半角数字にし、空白を取り除く
+fn normalize_digits(str: &str) -> String {
+    str.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '０'..='９' => char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// `3_2`のような条番号を「第3条の2」の形にする
+pub fn article_label(article: &str) -> String {
+    let mut nums = article.split('_');
+    let mut label = format!("第{}条", nums.next().unwrap_or_default());
+    for num in nums {
+        label.push_str(&format!("の{num}"));
+    }
+    label
+}
+
+/// 略称であれば正式名称に展開する
+pub fn expand_law_name_abbreviation(law_name: &str) -> String {
+    LAW_NAME_ABBREVIATIONS
+        .iter()
+        .find(|(abbreviation, _)| *abbreviation == law_name)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| law_name.to_string())
+}
+
+/// 「民法709条」のような引用全体の正規表現
+static CITATION_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    let number = NUMBER_PATTERN;
+    Regex::new(&format!(
+        "^(?<name>.*?)(?:第?(?<article>{number})条(?<branch>(?:の{number})*))?(?:第?(?<paragraph>{number})項)?(?:第?(?<item>{number})号)?$"
+    ))
+    .unwrap()
+});
+
+/// 「民法709条」「民法第七百九条第一項」のような引用を解析する
+pub fn parse_citation(cite: &str) -> Result<Citation, LawscapeCoreError> {
+    let cite = normalize_digits(cite);
+    let caps = CITATION_REGEX.captures(&cite).ok_or_else(|| {
+        LawscapeCoreError::InvalidParameter(format!("unsupported citation: {cite}"))
+    })?;
+    let law_name = caps
+        .name("name")
+        .map(|m| m.as_str().to_string())
+        .unwrap_or_default();
+    if law_name.is_empty() {
+        return Err(LawscapeCoreError::InvalidParameter(format!(
+            "law name is not found in citation: {cite}"
+        )));
+    }
+    let parse_number = |name: &str| -> Result<Option<usize>, LawscapeCoreError> {
+        caps.name(name)
+            .map(|m| {
+                parse_japanese_number(m.as_str()).ok_or_else(|| {
+                    LawscapeCoreError::InvalidParameter(format!("invalid number: {}", m.as_str()))
+                })
+            })
+            .transpose()
+    };
+    let article = match parse_number("article")? {
+        Some(article) => {
+            let mut num = article.to_string();
+            if let Some(branch) = caps.name("branch") {
+                for b in branch.as_str().split('の').filter(|s| !s.is_empty()) {
+                    let b = parse_japanese_number(b).ok_or_else(|| {
+                        LawscapeCoreError::InvalidParameter(format!("invalid number: {b}"))
+                    })?;
+                    num.push_str(&format!("_{b}"));
+                }
+            }
+            Some(num)
+        }
+        None => None,
+    };
+    Ok(Citation {
+        official_law_name: expand_law_name_abbreviation(&law_name),
+        law_name,
+        article,
+        paragraph: parse_number("paragraph")?,
+        item: parse_number("item")?,
+    })
+}
+
+/// 文章中の位置。バイト単位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TextSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 文章中で見つかった法令への参照
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StatuteReference {
+    pub span: TextSpan,
+    /// 文章中で参照にあたる部分
+    pub text: String,
+    /// 文章中に書かれていた法令名。「同法」の場合は直前に参照された法令名
+    pub law_name: Option<String>,
+    /// 略称を展開した法令名
+    pub official_law_name: Option<String>,
+    /// 条番号。「第三条の二」は`3_2`のように表す
+    pub article: Option<String>,
+    /// 項番号
+    pub paragraph: Option<usize>,
+    /// 号番号
+    pub item: Option<usize>,
+    /// 「同法」のように直前の参照から法令名を補ったかどうか
+    pub is_inferred: bool,
+}
+
+/// 判例の引用を解析した結果
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct PrecedentCitation {
+    /// 裁判所名。「最判」のような略記は「最高裁判所」のように展開する
+    pub court: String,
+    /// 元号
+    pub era: String,
+    /// 元号での年
+    pub era_year: usize,
+    /// 西暦での年
+    pub year: usize,
+    pub month: usize,
+    pub day: usize,
+    /// 判決・決定の別
+    pub kind: String,
+    /// 法廷。「第三小法廷」「大法廷」など
+    #[serde(default)]
+    pub bench: Option<String>,
+    /// 判例集の掲載箇所
+    #[serde(default)]
+    pub reporter: Option<ReporterCitation>,
+}
+
+/// 判例集の掲載箇所。「民集22巻13号3454頁」など
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct ReporterCitation {
+    /// 判例集の略称。「民集」「判時」など
+    pub reporter: String,
+    /// 巻
+    pub volume: Option<usize>,
+    /// 号
+    pub issue: Option<usize>,
+    /// 頁
+    pub page: usize,
+}
+
+/// 文章中で見つかった判例への参照
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PrecedentReference {
+    pub span: TextSpan,
+    /// 文章中で参照にあたる部分
+    pub text: String,
+    pub citation: PrecedentCitation,
+}
+
+/// 元号と元号での年から西暦での年を求める
+pub fn era_to_year(era: &str, era_year: usize) -> Option<usize> {
+    let era = match era {
+        "明治" => Era::Meiji,
+        "大正" => Era::Taisho,
+        "昭和" => Era::Showa,
+        "平成" => Era::Heisei,
+        "令和" => Era::Reiwa,
+        _ => return None,
+    };
+    Some(crate::era_offset(&era) + era_year)
+}
+
+/// 「最判」「東京地判」のような略記を裁判所名と判決・決定の別に分ける
+fn expand_court_abbreviation(court: &str) -> Option<(String, String)> {
+    let (court, kind) = if let Some(court) = court.strip_suffix('判') {
+        (court, "判決")
+    } else if let Some(court) = court.strip_suffix('決') {
+        (court, "決定")
+    } else {
+        return None;
+    };
+    let court = match court {
+        "最" | "最大" => "最高裁判所".to_string(),
+        "大" => "大審院".to_string(),
+        _ => {
+            if let Some(place) = court.strip_suffix('高') {
+                format!("{place}高等裁判所")
+            } else if let Some(place) = court.strip_suffix('地') {
+                format!("{place}地方裁判所")
+            } else if let Some(place) = court.strip_suffix('家') {
+                format!("{place}家庭裁判所")
+            } else if let Some(place) = court.strip_suffix('簡') {
+                format!("{place}簡易裁判所")
+            } else {
+                return None;
+            }
+        }
+    };
+    Some((court, kind.to_string()))
+}
+
+/// 「最高裁」「東京地裁」のような略称を正式な裁判所名にする
+fn expand_court_name(court: &str) -> String {
+    if court == "最高裁" {
+        "最高裁判所".to_string()
+    } else if let Some(place) = court.strip_suffix("高裁") {
+        format!("{place}高等裁判所")
+    } else if let Some(place) = court.strip_suffix("地裁") {
+        format!("{place}地方裁判所")
+    } else if let Some(place) = court.strip_suffix("家裁") {
+        format!("{place}家庭裁判所")
+    } else if let Some(place) = court.strip_suffix("簡裁") {
+        format!("{place}簡易裁判所")
+    } else {
+        court.to_string()
+    }
+}
+
+/// 判例集の略称
+const REPORTER_NAMES: [&str; 16] = [
+    "民集",
+    "刑集",
+    "集民",
+    "集刑",
+    "高民集",
+    "高刑集",
+    "下民集",
+    "下刑集",
+    "行集",
+    "家月",
+    "訟月",
+    "判時",
+    "判タ",
+    "労判",
+    "金判",
+    "金法",
+];
+
+/// 「第3小法廷」などを「第三小法廷」のように漢数字にそろえる
+fn normalize_bench(bench: &str) -> String {
+    let num = normalize_digits(bench).chars().find_map(|c| match c {
+        '1' | '一' => Some('一'),
+        '2' | '二' => Some('二'),
+        '3' | '三' => Some('三'),
+        _ => None,
+    });
+    match num {
+        Some(num) => format!("第{num}小法廷"),
+        None => bench.to_string(),
+    }
+}
+
+/// 判例の引用の正規表現
+static PRECEDENT_REFERENCE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    let number = NUMBER_PATTERN;
+    // 地名は漢字だけからなるものとし、「同旨の」「原審である」のような直前の語を含めない
+    let place = "[\\p{Han}々]{0,6}";
+    let bench = "第?[一二三1-3１-３]小法廷|大法廷";
+    let reporter = REPORTER_NAMES.join("|");
+    Regex::new(&format!(
+        "(?:(?<court>最高裁判所|最高裁|大審院|{place}(?:高等裁判所|地方裁判所|家庭裁判所|簡易裁判所|高裁|地裁|家裁|簡裁))|(?<court_abbr>最大判|最大決|最判|最決|大判|大決|{place}(?:高判|高決|地判|地決|家判|家決|簡判|簡決)))\
+        (?<bench_before>{bench})?\
+        (?<era>明治|大正|昭和|平成|令和)(?<year>{number}|元)(?:年|[・.．])(?<month>{number})(?:月|[・.．])(?<day>{number})日?\
+        (?<bench_after>{bench})?(?<kind>判決|決定)?\
+        (?:[・、，,\\s]?(?<reporter>{reporter})(?:(?<volume>{number})巻)?(?:(?<issue>{number})号)?(?<page>{number})頁)?"
+    ))
+    .unwrap()
+});
+
+/// 文章中の判例の引用を探す。
+/// 「最高裁昭和43年12月24日第三小法廷判決・民集22巻13号3454頁」「最判昭和43・12・24」のような形式に対応する。
+pub fn find_precedent_references(text: &str) -> Vec<PrecedentReference> {
+    PRECEDENT_REFERENCE_REGEX
+        .captures_iter(text)
+        .filter_map(|caps| {
+            let m = caps.get(0)?;
+            let (court, kind) = if let Some(court) = caps.name("court") {
+                let kind = caps.name("kind")?.as_str().to_string();
+                (expand_court_name(court.as_str()), kind)
+            } else {
+                expand_court_abbreviation(caps.name("court_abbr")?.as_str())?
+            };
+            let era = caps.name("era")?.as_str().to_string();
+            let era_year = match caps.name("year")?.as_str() {
+                "元" => 1,
+                y => parse_japanese_number(y)?,
+            };
+            let reporter = match caps.name("reporter") {
+                Some(reporter) => Some(ReporterCitation {
+                    reporter: reporter.as_str().to_string(),
+                    volume: caps
+                        .name("volume")
+                        .and_then(|v| parse_japanese_number(v.as_str())),
+                    issue: caps
+                        .name("issue")
+                        .and_then(|i| parse_japanese_number(i.as_str())),
+                    page: parse_japanese_number(caps.name("page")?.as_str())?,
+                }),
+                None => None,
+            };
+            let citation = PrecedentCitation {
+                court,
+                year: era_to_year(&era, era_year)?,
+                era,
+                era_year,
+                month: parse_japanese_number(caps.name("month")?.as_str())?,
+                day: parse_japanese_number(caps.name("day")?.as_str())?,
+                kind,
+                bench: caps
+                    .name("bench_before")
+                    .or(caps.name("bench_after"))
+                    .map(|b| normalize_bench(b.as_str())),
+                reporter,
+            };
+            Some(PrecedentReference {
+                span: TextSpan {
+                    start: m.start(),
+                    end: m.end(),
+                },
+                text: m.as_str().to_string(),
+                citation,
+            })
+        })
+        .collect()
+}
+
+/// 文章中の条・項・号の正規表現
+static STATUTE_ARTICLE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    let number = NUMBER_PATTERN;
+    Regex::new(&format!(
+        "第?(?<article>{number})条(?<branch>(?:の{number})*)(?:第?(?<paragraph>{number})項)?(?:第?(?<item>{number})号)?"
+    ))
+    .unwrap()
+});
+
+/// 文章中の略称の出現の終わりの位置を求める。
+/// 「破産」「特許」のように「法」で終わらない略称は「破産者」「特許権」のような語の一部と区別するため、
+/// 「法」か条番号が続く場合のみ法令名として扱う。「法」が続く場合は「法」までを法令名に含める。
+fn abbreviation_end(text: &str, abbreviation: &str, end: usize) -> Option<usize> {
+    if abbreviation.ends_with('法') {
+        return Some(end);
+    }
+    let following = &text[end..];
+    if following.starts_with('法') {
+        Some(end + '法'.len_utf8())
+    } else if ARTICLE_FOLLOWING_REGEX.is_match(following) {
+        Some(end)
+    } else {
+        None
+    }
+}
+
+/// 文章中の法令への参照を探す。
+/// law_namesに含まれる法令名と略称の出現と、それに続く条・項・号を参照として扱う。
+pub fn find_statute_references(text: &str, law_names: &[String]) -> Vec<StatuteReference> {
+    // 文章中の法令名の出現位置。同じ位置から始まる場合は長い方を優先する
+    let mut name_occurrences = law_names
+        .iter()
+        .flat_map(|name| {
+            text.match_indices(name.as_str())
+                .map(move |(start, s)| (start, start + s.len(), name.clone()))
+        })
+        .chain(
+            LAW_NAME_ABBREVIATIONS
+                .iter()
+                .flat_map(|(abbreviation, official_name)| {
+                    text.match_indices(abbreviation)
+                        .filter_map(move |(start, s)| {
+                            let end = abbreviation_end(text, abbreviation, start + s.len())?;
+                            Some((start, end, official_name.to_string()))
+                        })
+                }),
+        )
+        .collect::<Vec<_>>();
+    name_occurrences.sort_by(|(s1, e1, _), (s2, e2, _)| s1.cmp(s2).then(e2.cmp(e1)));
+    let mut names: Vec<(usize, usize, String)> = Vec::new();
+    for occurrence in name_occurrences {
+        if names.last().is_none_or(|(_, end, _)| *end <= occurrence.0) {
+            names.push(occurrence);
+        }
+    }
+
+    let mut references = Vec::new();
+    let mut used_names = vec![false; names.len()];
+    // 「同法」が指す法令名
+    let mut last_law_name: Option<(String, String)> = None;
+    let mut name_index = 0;
+    for caps in STATUTE_ARTICLE_REGEX.captures_iter(text) {
+        let Some(m) = caps.get(0) else {
+            continue;
+        };
+        // 条番号より前に現れた法令名のみを参照の対象にする
+        while name_index < names.len() && names[name_index].1 <= m.start() {
+            if names[name_index].1 < m.start() && !used_names[name_index] {
+                let (start, end, official_name) = &names[name_index];
+                last_law_name = Some((text[*start..*end].to_string(), official_name.clone()));
+            }
+            name_index += 1;
+        }
+        let (start, law_name, is_inferred) = match name_index
+            .checked_sub(1)
+            .filter(|i| names[*i].1 == m.start())
+        {
+            Some(i) => {
+                used_names[i] = true;
+                let (start, end, official_name) = &names[i];
+                let law_name = (text[*start..*end].to_string(), official_name.clone());
+                last_law_name = Some(law_name.clone());
+                (*start, Some(law_name), false)
+            }
+            None => match text[..m.start()].strip_suffix("同法") {
+                Some(prefix) if last_law_name.is_some() => {
+                    (prefix.len(), last_law_name.clone(), true)
+                }
+                _ => (m.start(), None, false),
+            },
+        };
+        let article = caps.name("article").and_then(|a| {
+            let mut num = parse_japanese_number(a.as_str())?.to_string();
+            if let Some(branch) = caps.name("branch") {
+                for b in branch.as_str().split('の').filter(|s| !s.is_empty()) {
+                    num.push_str(&format!("_{}", parse_japanese_number(b)?));
+                }
+            }
+            Some(num)
+        });
+        references.push(StatuteReference {
+            span: TextSpan {
+                start,
+                end: m.end(),
+            },
+            text: text[start..m.end()].to_string(),
+            official_law_name: law_name.as_ref().map(|(_, official)| official.clone()),
+            law_name: law_name.map(|(name, _)| name),
+            article,
+            paragraph: caps
+                .name("paragraph")
+                .and_then(|p| parse_japanese_number(p.as_str())),
+            item: caps
+                .name("item")
+                .and_then(|i| parse_japanese_number(i.as_str())),
+            is_inferred,
+        });
+    }
+    // 条番号を伴わない法令名の出現も参照として扱う
+    for (i, (start, end, official_name)) in names.iter().enumerate() {
+        if !used_names[i] {
+            references.push(StatuteReference {
+                span: TextSpan {
+                    start: *start,
+                    end: *end,
+                },
+                text: text[*start..*end].to_string(),
+                law_name: Some(text[*start..*end].to_string()),
+                official_law_name: Some(official_name.clone()),
+                article: None,
+                paragraph: None,
+                item: None,
+                is_inferred: false,
+            });
+        }
+    }
+    references.sort_by_key(|r| r.span.start);
+    references
+}
+
+/// 法令全体は`{法令ID}`、条は`{法令ID}:{条番号}`として参照先を表す。
+/// 法令の`reference_keys`属性や判例の`statute_keys`属性の値に使う
+pub fn statute_key(law_id: &str, article: Option<&str>) -> String {
+    match article {
+        Some(article) => format!("{law_id}:{article}"),
+        None => law_id.to_string(),
+    }
+}
+
+/// 法令名と法令IDの対応
+#[derive(Debug, Clone, Default)]
+pub struct LawNameTable {
+    ids: HashMap<String, String>,
+    /// 先頭の二文字ごとの法令名。文章に現れうる法令名を絞り込むために使う
+    names_by_prefix: HashMap<(char, char), Vec<String>>,
+}
+
+impl LawNameTable {
+    /// 法令名と法令IDの組から生成する
+    pub fn new(laws: impl IntoIterator<Item = (String, String)>) -> Self {
+        let ids = laws.into_iter().collect::<HashMap<String, String>>();
+        let names_by_prefix = group_by_prefix(ids.keys().cloned());
+        Self {
+            ids,
+            names_by_prefix,
+        }
+    }
+
+    /// 法令名のみから生成する。法令IDは求められないので、`candidates`で法令名を絞り込むためだけに使う
+    pub fn from_names(names: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            ids: HashMap::new(),
+            names_by_prefix: group_by_prefix(names),
+        }
+    }
+
+    pub fn get_law_id(&self, name: &str) -> Option<&String> {
+        self.ids.get(name)
+    }
+
+    /// 文章に現れる法令名の候補。`find_statute_references`に渡す
+    pub fn candidates(&self, text: &str) -> Vec<String> {
+        let chars = text.chars().collect::<Vec<char>>();
+        let mut names = chars
+            .windows(2)
+            .filter_map(|w| self.names_by_prefix.get(&(w[0], w[1])))
+            .flatten()
+            .cloned()
+            .collect::<Vec<String>>();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// 参照の法令名から法令IDを求める。略称を展開した法令名、書かれていた法令名の順に探す
+    pub fn resolve(&self, reference: &StatuteReference) -> Option<&String> {
+        reference
+            .official_law_name
+            .as_ref()
+            .and_then(|name| self.get_law_id(name))
+            .or_else(|| {
+                reference
+                    .law_name
+                    .as_ref()
+                    .and_then(|name| self.get_law_id(name))
+            })
+    }
+}
+
+/// 法令名を先頭の二文字ごとにまとめる
+fn group_by_prefix(names: impl IntoIterator<Item = String>) -> HashMap<(char, char), Vec<String>> {
+    let mut names_by_prefix: HashMap<(char, char), Vec<String>> = HashMap::new();
+    for name in names {
+        let mut chars = name.chars();
+        if let (Some(c1), Some(c2)) = (chars.next(), chars.next()) {
+            names_by_prefix.entry((c1, c2)).or_default().push(name);
+        }
+    }
+    names_by_prefix
+}
+
+/// 文章中の法令への参照から、参照先を`statute_key`の形で求める。
+/// `exclude_law_id`の法令への参照は除く。
+pub fn find_statute_keys(
+    text: &str,
+    law_names: &LawNameTable,
+    exclude_law_id: Option<&str>,
+) -> Vec<String> {
+    let mut keys = find_statute_references(text, &law_names.candidates(text))
+        .iter()
+        .filter_map(|reference| {
+            let law_id = law_names.resolve(reference)?;
+            if exclude_law_id == Some(law_id.as_str()) {
+                return None;
+            }
+            let mut keys = vec![statute_key(law_id, None)];
+            if let Some(article) = &reference.article {
+                keys.push(statute_key(law_id, Some(article)));
+            }
+            Some(keys)
+        })
+        .flatten()
+        .collect::<Vec<String>>();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_kanji_numbers() {
+        assert_eq!(parse_japanese_number("七百九"), Some(709));
+        assert_eq!(parse_japanese_number("千"), Some(1000));
+        assert_eq!(parse_japanese_number("十"), Some(10));
+        assert_eq!(parse_japanese_number("三十六"), Some(36));
+        assert_eq!(parse_japanese_number("千二百三十四"), Some(1234));
+        assert_eq!(parse_japanese_number("一万二千"), Some(12000));
+        assert_eq!(parse_japanese_number("二〇"), Some(20));
+    }
+
+    #[test]
+    fn parse_ascii_and_full_width_numbers() {
+        assert_eq!(parse_japanese_number("709"), Some(709));
+        assert_eq!(parse_japanese_number("７０９"), Some(709));
+        assert_eq!(parse_japanese_number(""), None);
+        assert_eq!(parse_japanese_number("条"), None);
+    }
+
+    #[test]
+    fn parse_citation_with_kanji_numbers() {
+        let citation = parse_citation("民法第七百九条第一項第二号").unwrap();
+        assert_eq!(
+            citation,
+            Citation {
+                law_name: "民法".to_string(),
+                official_law_name: "民法".to_string(),
+                article: Some("709".to_string()),
+                paragraph: Some(1),
+                item: Some(2),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_citation_with_full_width_digits() {
+        let citation = parse_citation("民法７０９条").unwrap();
+        assert_eq!(citation.law_name, "民法");
+        assert_eq!(citation.article.as_deref(), Some("709"));
+        assert_eq!(citation.paragraph, None);
+        assert_eq!(citation.item, None);
+    }
+
+    #[test]
+    fn parse_citation_with_branch_number() {
+        let citation = parse_citation("会社法第三条の二").unwrap();
+        assert_eq!(citation.article.as_deref(), Some("3_2"));
+        let citation = parse_citation("地方税法施行令第七条の三の二第1項").unwrap();
+        assert_eq!(citation.law_name, "地方税法施行令");
+        assert_eq!(citation.article.as_deref(), Some("7_3_2"));
+        assert_eq!(citation.paragraph, Some(1));
+        assert_eq!(article_label("7_3_2"), "第7条の3の2");
+    }
+
+    #[test]
+    fn parse_citation_expands_abbreviation() {
+        let citation = parse_citation("民訴法248条").unwrap();
+        assert_eq!(citation.law_name, "民訴法");
+        assert_eq!(citation.official_law_name, "民事訴訟法");
+        assert_eq!(citation.article.as_deref(), Some("248"));
+        // 略称と完全に一致する場合だけ展開する
+        let citation = parse_citation("破産法").unwrap();
+        assert_eq!(citation.official_law_name, "破産法");
+        assert_eq!(citation.article, None);
+    }
+
+    #[test]
+    fn parse_citation_keeps_numbers_in_law_name() {
+        // 法令番号の形の名前は、末尾の条番号だけを条として扱う
+        let citation = parse_citation("平成十一年法律第百号第三条").unwrap();
+        assert_eq!(citation.law_name, "平成十一年法律第百号");
+        assert_eq!(citation.article.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn parse_citation_without_law_name() {
+        assert!(matches!(
+            parse_citation("709条"),
+            Err(LawscapeCoreError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            parse_citation(""),
+            Err(LawscapeCoreError::InvalidParameter(_))
+        ));
+    }
+
+    fn law_names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn find_statute_references_with_same_law() {
+        let text = "民法709条及び同法710条に基づき損害賠償を請求する。";
+        let references = find_statute_references(text, &law_names(&["民法"]));
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].text, "民法709条");
+        assert_eq!(references[0].law_name.as_deref(), Some("民法"));
+        assert_eq!(references[0].article.as_deref(), Some("709"));
+        assert!(!references[0].is_inferred);
+        assert_eq!(references[1].text, "同法710条");
+        assert_eq!(references[1].law_name.as_deref(), Some("民法"));
+        assert_eq!(references[1].article.as_deref(), Some("710"));
+        assert!(references[1].is_inferred);
+        let span = references[1].span;
+        assert_eq!(&text[span.start..span.end], "同法710条");
+    }
+
+    #[test]
+    fn find_statute_references_prefers_longer_name() {
+        let text = "民事訴訟法施行規則第三条の二第一項";
+        let references =
+            find_statute_references(text, &law_names(&["民事訴訟法", "民事訴訟法施行規則"]));
+        assert_eq!(references.len(), 1);
+        assert_eq!(
+            references[0].law_name.as_deref(),
+            Some("民事訴訟法施行規則")
+        );
+        assert_eq!(references[0].article.as_deref(), Some("3_2"));
+        assert_eq!(references[0].paragraph, Some(1));
+    }
+
+    #[test]
+    fn find_statute_references_expands_abbreviation() {
+        let references = find_statute_references("労基法36条による協定", &[]);
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].law_name.as_deref(), Some("労基法"));
+        assert_eq!(
+            references[0].official_law_name.as_deref(),
+            Some("労働基準法")
+        );
+        assert_eq!(references[0].article.as_deref(), Some("36"));
+    }
+
+    #[test]
+    fn find_statute_references_ignores_short_abbreviation_in_word() {
+        assert!(find_statute_references("破産者が特許権を有する", &[]).is_empty());
+        let references = find_statute_references("破産法2条と特許2条", &[]);
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].law_name.as_deref(), Some("破産法"));
+        assert_eq!(references[0].official_law_name.as_deref(), Some("破産法"));
+        assert_eq!(references[0].article.as_deref(), Some("2"));
+        assert_eq!(references[1].law_name.as_deref(), Some("特許"));
+        assert_eq!(references[1].official_law_name.as_deref(), Some("特許法"));
+    }
+
+    #[test]
+    fn law_name_table_from_names_narrows_candidates() {
+        let table = LawNameTable::from_names(law_names(&["民法", "民事訴訟法", "刑法", "商"]));
+        assert_eq!(
+            table.candidates("民事訴訟法第三条と民法七百九条"),
+            vec!["民事訴訟法", "民法"]
+        );
+        assert!(table.candidates("刑事訴訟法").is_empty());
+        // 法令IDは持たない
+        assert_eq!(table.get_law_id("民法"), None);
+    }
+
+    #[test]
+    fn find_statute_references_without_article() {
+        let references = find_statute_references("民法の規定と第三条", &law_names(&["民法"]));
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].text, "民法");
+        assert_eq!(references[0].article, None);
+        // 法令名が直前に無い条番号は法令名を持たない
+        assert_eq!(references[1].text, "第三条");
+        assert_eq!(references[1].law_name, None);
+    }
+
+    #[test]
+    fn find_precedent_references_with_abbreviation() {
+        let references = find_precedent_references("最判平成元・3・8の判断による");
+        assert_eq!(references.len(), 1);
+        let citation = &references[0].citation;
+        assert_eq!(citation.court, "最高裁判所");
+        assert_eq!(citation.kind, "判決");
+        assert_eq!((citation.era.as_str(), citation.era_year), ("平成", 1));
+        assert_eq!((citation.year, citation.month, citation.day), (1989, 3, 8));
+        assert_eq!(citation.reporter, None);
+    }
+
+    #[test]
+    fn find_precedent_references_with_district_court() {
+        let text = "東京地判令和2年1月30日判時2466号62頁";
+        let references = find_precedent_references(text);
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].text, text);
+        let citation = &references[0].citation;
+        assert_eq!(citation.court, "東京地方裁判所");
+        assert_eq!((citation.year, citation.month, citation.day), (2020, 1, 30));
+        assert_eq!(
+            citation.reporter,
+            Some(ReporterCitation {
+                reporter: "判時".to_string(),
+                volume: None,
+                issue: Some(2466),
+                page: 62,
+            })
+        );
+    }
+
+    #[test]
+    fn find_precedent_references_with_full_citation() {
+        let text = "最高裁昭和43年12月24日第三小法廷判決・民集22巻13号3454頁";
+        let references = find_precedent_references(text);
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].text, text);
+        assert_eq!(
+            references[0].span,
+            TextSpan {
+                start: 0,
+                end: text.len()
+            }
+        );
+        let citation = &references[0].citation;
+        assert_eq!(citation.court, "最高裁判所");
+        assert_eq!(citation.kind, "判決");
+        assert_eq!((citation.era.as_str(), citation.era_year), ("昭和", 43));
+        assert_eq!(
+            (citation.year, citation.month, citation.day),
+            (1968, 12, 24)
+        );
+        assert_eq!(citation.bench.as_deref(), Some("第三小法廷"));
+        assert_eq!(
+            citation.reporter,
+            Some(ReporterCitation {
+                reporter: "民集".to_string(),
+                volume: Some(22),
+                issue: Some(13),
+                page: 3454,
+            })
+        );
+    }
+
+    #[test]
+    fn find_precedent_references_with_reporter_without_issue() {
+        let references = find_precedent_references("最大判昭和48年4月4日刑集27巻265頁参照");
+        assert_eq!(references.len(), 1);
+        let citation = &references[0].citation;
+        assert_eq!(citation.bench, None);
+        assert_eq!(
+            citation.reporter,
+            Some(ReporterCitation {
+                reporter: "刑集".to_string(),
+                volume: Some(27),
+                issue: None,
+                page: 265,
+            })
+        );
+        assert_eq!(references[0].text, "最大判昭和48年4月4日刑集27巻265頁");
+    }
+
+    #[test]
+    fn normalize_bench_to_kanji() {
+        assert_eq!(normalize_bench("第3小法廷"), "第三小法廷");
+        assert_eq!(normalize_bench("第２小法廷"), "第二小法廷");
+        assert_eq!(normalize_bench("一小法廷"), "第一小法廷");
+        assert_eq!(normalize_bench("大法廷"), "大法廷");
+    }
+
+    #[test]
+    fn find_precedent_references_requires_kind() {
+        // 裁判所名で書かれている場合は判決・決定の別が無いと引用として扱わない
+        assert!(find_precedent_references("東京地裁令和2年1月30日").is_empty());
+    }
+
+    #[test]
+    fn find_precedent_references_after_prose() {
+        let text = "同旨の大阪地判令和2年1月30日がある。";
+        let references = find_precedent_references(text);
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].text, "大阪地判令和2年1月30日");
+        assert_eq!(references[0].span.start, "同旨の".len());
+        assert_eq!(references[0].citation.court, "大阪地方裁判所");
+
+        let references =
+            find_precedent_references("原審である東京高等裁判所平成10年5月20日判決を取り消す");
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].text, "東京高等裁判所平成10年5月20日判決");
+        assert_eq!(references[0].citation.court, "東京高等裁判所");
+
+        let references = find_precedent_references("参照：名古屋高決平成5年3月1日");
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].text, "名古屋高決平成5年3月1日");
+        assert_eq!(references[0].citation.court, "名古屋高等裁判所");
+        assert_eq!(references[0].citation.kind, "決定");
+    }
+}
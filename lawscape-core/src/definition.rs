@@ -0,0 +1,372 @@
+//! 法令の定義規定から用語の定義を取り出す
+
+use crate::Law;
+use crate::structure::{ArticleItem, ArticleParagraph};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+/// 定義の書き方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefinitionKind {
+    /// 「この法律において「個人情報」とは、〜をいう。」「「X」とは、〜をいい、」
+    Sentence,
+    /// 「〜（以下「X」という。）」
+    Parenthetical,
+    /// 「次の各号に掲げる用語の意義は、当該各号に定めるところによる。」に続く号
+    Item,
+}
+
+/// 条文の中の用語の定義
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct TermDefinition {
+    /// 定義されている用語
+    pub term: String,
+    /// 定義の本文
+    pub definition: String,
+    /// 定義が適用される範囲。「この法律」「この章」など。書かれていない場合は`None`
+    pub scope: Option<String>,
+    pub kind: DefinitionKind,
+    /// 項番号
+    pub paragraph: String,
+    /// 号番号
+    pub item: Option<String>,
+}
+
+/// 一つの用語として扱う長さの上限
+const TERM_MAX_LENGTH: usize = 40;
+
+/// 定義が適用される範囲の書き方
+const SCOPE_PATTERN: &str = r"(?:この[^「」、。\n]{1,12}?|前[条項]|次[条項]|第[〇一二三四五六七八九十百千]+[条章節][^「」、。\n]*?)";
+
+static SENTENCE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(?:(?<scope>{SCOPE_PATTERN})において、?)?(?<terms>(?:「[^」\n]+」(?:又は|若しくは|及び|並びに|、)?)+)とは、(?<definition>[^\n]+?)をい(?:う。|い、)"
+    ))
+    .unwrap()
+});
+
+static PARENTHETICAL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(?<definition>[^、。「」（）\n]+)（以下(?:(?<scope>{SCOPE_PATTERN})において)?(?:単に)?(?<terms>(?:「[^」\n]+」(?:又は|若しくは|及び|並びに|、)?)+)(?:という|と総称する)。）"
+    ))
+    .unwrap()
+});
+
+static ITEM_HEADER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(?:(?<scope>{SCOPE_PATTERN})において、?)?次の各号に掲げる用語の意義は"
+    ))
+    .unwrap()
+});
+
+static TERM_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"「(?<term>[^」]+)」").unwrap());
+
+/// 文章中の「「X」とは、〜をいう。」「〜（以下「X」という。）」の形の定義を探す
+fn find_sentence_definitions(
+    text: &str,
+    paragraph: &str,
+    item: Option<&str>,
+    definitions: &mut Vec<TermDefinition>,
+) {
+    let patterns = [
+        (&*SENTENCE_REGEX, DefinitionKind::Sentence),
+        (&*PARENTHETICAL_REGEX, DefinitionKind::Parenthetical),
+    ];
+    let mut found = Vec::new();
+    for (re, kind) in patterns {
+        for caps in re.captures_iter(text) {
+            found.push((caps.get(0).unwrap().start(), caps, kind));
+        }
+    }
+    // 条文に現れた順に並べる
+    found.sort_by_key(|(start, _, _)| *start);
+    for (_, caps, kind) in found {
+        let scope = caps.name("scope").map(|m| m.as_str().to_string());
+        let definition = caps["definition"].to_string();
+        for term_caps in TERM_REGEX.captures_iter(&caps["terms"]) {
+            let term = &term_caps["term"];
+            if TERM_MAX_LENGTH < term.chars().count() {
+                continue;
+            }
+            definitions.push(TermDefinition {
+                term: term.to_string(),
+                definition: definition.clone(),
+                scope: scope.clone(),
+                kind,
+                paragraph: paragraph.to_string(),
+                item: item.map(|s| s.to_string()),
+            });
+        }
+    }
+}
+
+/// 号の本文を用語と定義に分ける。用語と定義は空白で区切られている
+fn split_item_definition(item: &ArticleItem) -> Option<(String, String)> {
+    let text = item.text.trim();
+    let (term, definition) = text.split_once(['\u{3000}', ' '])?;
+    let term = term.trim_matches(['「', '」']);
+    let definition = definition.trim();
+    if term.is_empty() || definition.is_empty() || TERM_MAX_LENGTH < term.chars().count() {
+        return None;
+    }
+    Some((term.to_string(), definition.to_string()))
+}
+
+/// 号とその細分の本文を全て集める
+fn item_texts(item: &ArticleItem) -> Vec<&str> {
+    let mut texts = vec![item.text.as_str()];
+    for child in item.children.iter() {
+        texts.extend(item_texts(child));
+    }
+    texts
+}
+
+/// 条文の項と号から用語の定義を取り出す。条文に現れた順に並ぶ
+pub fn extract_definitions(paragraphs: &[ArticleParagraph]) -> Vec<TermDefinition> {
+    let mut definitions = Vec::new();
+    for paragraph in paragraphs.iter() {
+        find_sentence_definitions(&paragraph.text, &paragraph.num, None, &mut definitions);
+        if let Some(caps) = ITEM_HEADER_REGEX.captures(&paragraph.text) {
+            let scope = caps.name("scope").map(|m| m.as_str().to_string());
+            for item in paragraph.items.iter() {
+                if let Some((term, definition)) = split_item_definition(item) {
+                    definitions.push(TermDefinition {
+                        term,
+                        definition,
+                        scope: scope.clone(),
+                        kind: DefinitionKind::Item,
+                        paragraph: paragraph.num.clone(),
+                        item: Some(item.num.clone()),
+                    });
+                }
+            }
+        } else {
+            for item in paragraph.items.iter() {
+                for text in item_texts(item) {
+                    find_sentence_definitions(
+                        text,
+                        &paragraph.num,
+                        Some(&item.num),
+                        &mut definitions,
+                    );
+                }
+            }
+        }
+    }
+    definitions
+}
+
+/// 定義の一覧から、絞り込みに使う用語の一覧を求める
+pub fn defined_terms(definitions: &[TermDefinition]) -> Vec<String> {
+    let mut terms = definitions
+        .iter()
+        .map(|d| d.term.clone())
+        .collect::<Vec<String>>();
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+/// 法令の中で定義されている用語
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DefinedTerm {
+    pub law_id: String,
+    pub law_name: String,
+    /// 定義している条の条番号
+    pub article: String,
+    /// 定義している条文の文書のID
+    pub document_id: String,
+    #[serde(flatten)]
+    pub definition: TermDefinition,
+}
+
+impl DefinedTerm {
+    /// 条文の中の定義の一覧を求める
+    pub fn from_law(law: &Law) -> Vec<Self> {
+        law.definitions
+            .iter()
+            .map(|definition| DefinedTerm {
+                law_id: law.law_id.to_string(),
+                law_name: law.name.clone(),
+                article: law.index.article.clone(),
+                document_id: law.id.clone(),
+                definition: definition.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paragraph(num: &str, text: &str, items: Vec<ArticleItem>) -> ArticleParagraph {
+        ArticleParagraph {
+            num: num.to_string(),
+            text: text.to_string(),
+            items,
+        }
+    }
+
+    fn item(num: &str, text: &str) -> ArticleItem {
+        ArticleItem {
+            num: num.to_string(),
+            text: text.to_string(),
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn sentence_definition_with_scope() {
+        let definitions = extract_definitions(&[paragraph(
+            "1",
+            "この法律において「個人情報」とは、生存する個人に関する情報をいう。",
+            Vec::new(),
+        )]);
+        assert_eq!(
+            definitions,
+            vec![TermDefinition {
+                term: "個人情報".to_string(),
+                definition: "生存する個人に関する情報".to_string(),
+                scope: Some("この法律".to_string()),
+                kind: DefinitionKind::Sentence,
+                paragraph: "1".to_string(),
+                item: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn sentence_definitions_joined_by_wo_ii() {
+        let definitions = extract_definitions(&[paragraph(
+            "2",
+            "第二章において「事業者」とは、事業を行う者をいい、「消費者」又は「個人」とは、事業者以外の個人をいう。",
+            Vec::new(),
+        )]);
+        let terms = definitions
+            .iter()
+            .map(|d| (d.term.as_str(), d.definition.as_str(), d.scope.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            terms,
+            vec![
+                ("事業者", "事業を行う者", Some("第二章")),
+                ("消費者", "事業者以外の個人", None),
+                ("個人", "事業者以外の個人", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn parenthetical_definitions() {
+        let definitions = extract_definitions(&[paragraph(
+            "1",
+            "国は、個人情報の適正な取扱いを確保するための措置（以下「措置」という。）を講ずるとともに、地方公共団体の機関（以下この章において単に「機関」という。）と協力する。",
+            Vec::new(),
+        )]);
+        let terms = definitions
+            .iter()
+            .map(|d| {
+                (
+                    d.term.as_str(),
+                    d.definition.as_str(),
+                    d.scope.as_deref(),
+                    d.kind,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            terms,
+            vec![
+                (
+                    "措置",
+                    "個人情報の適正な取扱いを確保するための措置",
+                    None,
+                    DefinitionKind::Parenthetical
+                ),
+                (
+                    "機関",
+                    "地方公共団体の機関",
+                    Some("この章"),
+                    DefinitionKind::Parenthetical
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn item_definitions_after_header() {
+        let definitions = extract_definitions(&[paragraph(
+            "1",
+            "この法律において、次の各号に掲げる用語の意義は、当該各号に定めるところによる。",
+            vec![
+                item("1", "行政機関　内閣府、宮内庁その他の機関"),
+                item("2", "「独立行政法人等」 別表第一に掲げる法人"),
+                item("3", "区切りのない号"),
+            ],
+        )]);
+        assert_eq!(
+            definitions,
+            vec![
+                TermDefinition {
+                    term: "行政機関".to_string(),
+                    definition: "内閣府、宮内庁その他の機関".to_string(),
+                    scope: Some("この法律".to_string()),
+                    kind: DefinitionKind::Item,
+                    paragraph: "1".to_string(),
+                    item: Some("1".to_string()),
+                },
+                TermDefinition {
+                    term: "独立行政法人等".to_string(),
+                    definition: "別表第一に掲げる法人".to_string(),
+                    scope: Some("この法律".to_string()),
+                    kind: DefinitionKind::Item,
+                    paragraph: "1".to_string(),
+                    item: Some("2".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn sentence_definitions_in_items() {
+        let mut parent = item("1", "次に掲げる者");
+        parent.children.push(item(
+            "1",
+            "前項において「代理人」とは、本人に代わって行為をする者をいう。",
+        ));
+        let definitions =
+            extract_definitions(&[paragraph("3", "次に掲げる者をいう。", vec![parent])]);
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].term, "代理人");
+        assert_eq!(definitions[0].scope.as_deref(), Some("前項"));
+        assert_eq!(definitions[0].paragraph, "3");
+        assert_eq!(definitions[0].item.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn split_item_definition_rejects_long_terms() {
+        assert_eq!(
+            split_item_definition(&item("1", "「事業者」\u{3000}事業を行う者")),
+            Some(("事業者".to_string(), "事業を行う者".to_string()))
+        );
+        let long_term = "あ".repeat(TERM_MAX_LENGTH + 1);
+        assert_eq!(
+            split_item_definition(&item("1", &format!("{long_term} 定義"))),
+            None
+        );
+        assert_eq!(split_item_definition(&item("1", "用語　")), None);
+    }
+
+    #[test]
+    fn defined_terms_are_sorted_and_unique() {
+        let definitions = extract_definitions(&[paragraph(
+            "1",
+            "「乙」とは、二をいい、「甲」とは、一をいい、「乙」とは、三をいう。",
+            Vec::new(),
+        )]);
+        assert_eq!(defined_terms(&definitions), vec!["乙", "甲"]);
+    }
+}
@@ -0,0 +1,225 @@
+//! 法律から政令・府省令への委任の関係を求める
+
+use crate::Law;
+use crate::citation::LawNameTable;
+use crate::law_metadata::LawType;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+/// 法令名の末尾と、委任元の法令名の末尾の候補。候補は優先する順に並ぶ
+const DELEGATED_NAME_SUFFIXES: [(&str, &[&str]); 3] = [
+    ("施行細則", &["施行規則", "施行令", ""]),
+    ("施行規則", &["施行令", ""]),
+    ("施行令", &[""]),
+];
+
+/// 「〇〇法第三十六条第一項の規定に基づく〜」「〇〇法に基づく〜に関する省令」のような、委任元の法令名を含む法令名
+static DELEGATING_NAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?<name>.+?(?:法律|法|令|規則))(?:第[〇一二三四五六七八九十百千]+条[〇一二三四五六七八九十百千第条項号の及び並びに、]*)?(?:(?:の規定)?に基づ|の規定によ|に規定する|の施行に関する|の施行期日を定める|を実施するため|に関する.*(?:政令|省令|府令|規則)$)",
+    )
+    .unwrap()
+});
+
+/// 条文中の委任の文言
+static DELEGATION_PHRASE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?<kind>政令|省令|府令|規則)(?:で|により)定める").unwrap());
+
+/// 法令名から委任元の法令名の候補を求める。候補は優先する順に並ぶ。
+/// 「〇〇法施行規則」の場合は「〇〇法施行令」「〇〇法」の順になる。
+/// 末尾から分からない場合は「〇〇法の規定に基づく」「〇〇法の施行に関する」などの文言から求める。
+pub fn delegating_law_names(name: &str) -> Vec<String> {
+    for (suffix, parent_suffixes) in DELEGATED_NAME_SUFFIXES {
+        if let Some(base) = name.strip_suffix(suffix) {
+            if base.is_empty() {
+                return Vec::new();
+            }
+            return parent_suffixes
+                .iter()
+                .map(|parent_suffix| format!("{base}{parent_suffix}"))
+                .collect();
+        }
+    }
+    DELEGATING_NAME_REGEX
+        .captures(name)
+        .map(|caps| vec![caps["name"].to_string()])
+        .unwrap_or_default()
+}
+
+/// 法令名から委任元の法令IDを求める
+pub fn find_delegating_law(name: &str, law_names: &LawNameTable) -> Option<String> {
+    delegating_law_names(name)
+        .iter()
+        .find_map(|parent_name| law_names.get_law_id(parent_name))
+        .cloned()
+}
+
+/// 法令名から、`parent_name`の法令が委任元にあたるかどうかを判定する
+pub fn is_delegated_by(name: &str, parent_name: &str) -> bool {
+    delegating_law_names(name)
+        .iter()
+        .any(|candidate| candidate == parent_name)
+}
+
+/// 条文中の「政令で定める」「厚生労働省令で定める」などの文言から、委任先の法令の種類を求める。
+/// 現れた順に並び、重複は取り除く。
+pub fn find_delegated_law_types(text: &str) -> Vec<LawType> {
+    let mut law_types = Vec::new();
+    for caps in DELEGATION_PHRASE_REGEX.captures_iter(text) {
+        let law_type = match &caps["kind"] {
+            "政令" => LawType::CabinetOrder,
+            "省令" | "府令" => LawType::MinisterialOrdinance,
+            _ => LawType::Rule,
+        };
+        if !law_types.contains(&law_type) {
+            law_types.push(law_type);
+        }
+    }
+    law_types
+}
+
+/// 委任元の法令の条文のうち、委任先の法令の種類への委任の文言を含む条の条番号を出現順に求める。
+/// 委任先の法令の種類が分からない場合は、委任の文言を含む全ての条を返す。
+pub fn delegating_articles(articles: &[Law], law_type: Option<LawType>) -> Vec<String> {
+    select_delegating_articles(
+        articles.iter().map(|article| {
+            (
+                article.index.article.as_str(),
+                article.delegated_law_types.as_slice(),
+            )
+        }),
+        law_type,
+    )
+}
+
+/// 条番号と、その条の委任先の法令の種類の組から、委任の文言を含む条の条番号を出現順に求める
+fn select_delegating_articles<'a>(
+    articles: impl IntoIterator<Item = (&'a str, &'a [LawType])>,
+    law_type: Option<LawType>,
+) -> Vec<String> {
+    let mut article_list: Vec<String> = Vec::new();
+    for (article, delegated_law_types) in articles {
+        let is_delegating = match law_type {
+            Some(law_type) => delegated_law_types.contains(&law_type),
+            None => !delegated_law_types.is_empty(),
+        };
+        if is_delegating && !article_list.iter().any(|a| a == article) {
+            article_list.push(article.to_string());
+        }
+    }
+    article_list
+}
+
+/// 委任の階層の中の法令
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DelegationNode {
+    pub law_id: String,
+    pub name: String,
+    pub law_type: Option<LawType>,
+    /// 委任元の法令のうち、この法令の種類への委任の文言を含む条の条番号
+    pub delegating_articles: Vec<String>,
+    /// 委任先の法令。法令IDの順に並ぶ
+    pub children: Vec<DelegationNode>,
+}
+
+impl DelegationNode {
+    /// 法令の条文から委任先を含まないノードを作る
+    pub fn from_law(law: &Law, delegating_articles: Vec<String>) -> Self {
+        Self {
+            law_id: law.law_id.to_string(),
+            name: law.name.clone(),
+            law_type: law.law_type,
+            delegating_articles,
+            children: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delegating_law_names_from_suffix() {
+        assert_eq!(
+            delegating_law_names("建築基準法施行規則"),
+            vec!["建築基準法施行令", "建築基準法"]
+        );
+        assert_eq!(
+            delegating_law_names("地方自治法施行細則"),
+            vec!["地方自治法施行規則", "地方自治法施行令", "地方自治法"]
+        );
+        assert_eq!(
+            delegating_law_names("個人情報の保護に関する法律施行令"),
+            vec!["個人情報の保護に関する法律"]
+        );
+        assert!(delegating_law_names("施行令").is_empty());
+        assert!(delegating_law_names("民法").is_empty());
+    }
+
+    #[test]
+    fn delegating_law_names_from_phrase() {
+        assert_eq!(
+            delegating_law_names("労働基準法第三十六条第一項の規定に基づく協定の基準"),
+            vec!["労働基準法"]
+        );
+        assert_eq!(
+            delegating_law_names(
+                "建築基準法に基づく指定建築基準適合判定資格者検定機関等に関する省令"
+            ),
+            vec!["建築基準法"]
+        );
+        assert_eq!(
+            delegating_law_names("特定商取引に関する法律の規定による承諾の方法に関する省令"),
+            vec!["特定商取引に関する法律"]
+        );
+        assert_eq!(
+            delegating_law_names("民法の一部を改正する法律の施行に関する政令"),
+            vec!["民法の一部を改正する法律"]
+        );
+        assert_eq!(
+            delegating_law_names("電波法に関する手数料の額を定める政令"),
+            vec!["電波法"]
+        );
+        assert!(delegating_law_names("個人情報の保護に関する法律").is_empty());
+    }
+
+    #[test]
+    fn is_delegated_by_checks_candidates() {
+        assert!(is_delegated_by("建築基準法施行規則", "建築基準法"));
+        assert!(!is_delegated_by("建築基準法施行規則", "建築士法"));
+    }
+
+    #[test]
+    fn find_delegated_law_types_in_order() {
+        let text = "厚生労働省令で定める事項のほか、政令で定める基準に従い、政令により定める者及び内閣府令で定める者";
+        assert_eq!(
+            find_delegated_law_types(text),
+            vec![LawType::MinisterialOrdinance, LawType::CabinetOrder]
+        );
+        assert_eq!(
+            find_delegated_law_types("人事院規則で定める"),
+            vec![LawType::Rule]
+        );
+        assert!(find_delegated_law_types("前条の規定を準用する").is_empty());
+    }
+
+    #[test]
+    fn select_delegating_articles_by_law_type() {
+        let cabinet = [LawType::CabinetOrder];
+        let both = [LawType::CabinetOrder, LawType::MinisterialOrdinance];
+        let articles: [(&str, &[LawType]); 4] =
+            [("1", &[]), ("3", &both), ("2", &cabinet), ("3", &both)];
+        assert_eq!(
+            select_delegating_articles(articles, Some(LawType::CabinetOrder)),
+            vec!["3", "2"]
+        );
+        assert_eq!(
+            select_delegating_articles(articles, Some(LawType::MinisterialOrdinance)),
+            vec!["3"]
+        );
+        assert_eq!(select_delegating_articles(articles, None), vec!["3", "2"]);
+        assert!(select_delegating_articles(articles, Some(LawType::Rule)).is_empty());
+    }
+}
@@ -0,0 +1,148 @@
+//! 文書や検索語を埋め込みベクトルに変換する
+
+use std::fmt::Debug;
+
+/// 文章を固定長のベクトルに変換する
+pub trait Embedder: Debug + Send + Sync {
+    /// ベクトルの次元
+    fn dimensions(&self) -> usize;
+    /// 文章をベクトルに変換する。返すベクトルの長さは`dimensions`と一致させる
+    fn embed(&self, text: &str) -> Vec<f32>;
+}
+
+/// `HashedNgramEmbedder`のデフォルトの次元
+const DEFAULT_DIMENSIONS: usize = 512;
+/// `HashedNgramEmbedder`のデフォルトのn-gramの長さの範囲
+const DEFAULT_NGRAM_RANGE: (usize, usize) = (1, 3);
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// 文字n-gramをハッシュ値によって次元に割り当てるベクトル化。
+/// 外部のモデルを使わずCPUのみで動き、同じ文章からは常に同じベクトルを生成する。
+#[derive(Debug, Clone)]
+pub struct HashedNgramEmbedder {
+    dimensions: usize,
+    min_n: usize,
+    max_n: usize,
+}
+
+impl HashedNgramEmbedder {
+    /// 次元と、使うn-gramの長さの範囲を指定して生成する
+    pub fn new(dimensions: usize, min_n: usize, max_n: usize) -> Self {
+        Self {
+            dimensions,
+            min_n: min_n.max(1),
+            max_n: max_n.max(min_n.max(1)),
+        }
+    }
+}
+
+impl Default for HashedNgramEmbedder {
+    fn default() -> Self {
+        let (min_n, max_n) = DEFAULT_NGRAM_RANGE;
+        Self::new(DEFAULT_DIMENSIONS, min_n, max_n)
+    }
+}
+
+/// 実行環境によらず同じ値になるようにFNV-1aでハッシュ値を求める
+fn fnv1a(chars: &[char]) -> u64 {
+    let mut buf = [0; 4];
+    chars.iter().fold(FNV_OFFSET_BASIS, |hash, c| {
+        c.encode_utf8(&mut buf)
+            .bytes()
+            .fold(hash, |hash, b| (hash ^ b as u64).wrapping_mul(FNV_PRIME))
+    })
+}
+
+impl Embedder for HashedNgramEmbedder {
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn embed(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0_f32; self.dimensions];
+        if self.dimensions == 0 {
+            return vector;
+        }
+        // 句読点や空白をまたぐn-gramは作らない
+        for segment in text.split(|c: char| !c.is_alphanumeric()) {
+            let chars = segment.chars().collect::<Vec<char>>();
+            for n in self.min_n..=self.max_n {
+                for ngram in chars.windows(n) {
+                    let hash = fnv1a(ngram);
+                    let index = (hash % self.dimensions as u64) as usize;
+                    // 衝突による偏りを打ち消すため最上位ビットで符号を決める
+                    let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+                    vector[index] += sign;
+                }
+            }
+        }
+        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if 0.0 < norm {
+            vector.iter_mut().for_each(|v| *v /= norm);
+        }
+        vector
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn norm(vector: &[f32]) -> f32 {
+        vector.iter().map(|v| v * v).sum::<f32>().sqrt()
+    }
+
+    #[test]
+    fn same_text_gives_same_vector() {
+        let embedder = HashedNgramEmbedder::default();
+        let text = "故意又は過失によって他人の権利を侵害した者は";
+        assert_eq!(embedder.embed(text), embedder.embed(text));
+        assert_eq!(
+            embedder.embed(text),
+            HashedNgramEmbedder::default().embed(text)
+        );
+        assert_ne!(embedder.embed(text), embedder.embed("損害賠償"));
+    }
+
+    #[test]
+    fn vector_has_configured_dimensions() {
+        let embedder = HashedNgramEmbedder::new(64, 2, 2);
+        assert_eq!(embedder.dimensions(), 64);
+        assert_eq!(embedder.embed("個人情報").len(), 64);
+        assert_eq!(
+            HashedNgramEmbedder::default().embed("個人情報").len(),
+            DEFAULT_DIMENSIONS
+        );
+        assert!(
+            HashedNgramEmbedder::new(0, 1, 3)
+                .embed("個人情報")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn vector_is_normalized() {
+        let embedder = HashedNgramEmbedder::default();
+        for text in ["民法", "個人情報の保護に関する法律", "a"] {
+            assert!((norm(&embedder.embed(text)) - 1.0).abs() < 1e-5, "{text}");
+        }
+        // n-gramが作れない場合は零ベクトルになる
+        assert_eq!(norm(&embedder.embed("、。")), 0.0);
+    }
+
+    #[test]
+    fn ngram_range_is_clamped() {
+        let embedder = HashedNgramEmbedder::new(16, 0, 0);
+        assert_eq!((embedder.min_n, embedder.max_n), (1, 1));
+        let embedder = HashedNgramEmbedder::new(16, 3, 2);
+        assert_eq!((embedder.min_n, embedder.max_n), (3, 3));
+    }
+
+    #[test]
+    fn ngrams_do_not_cross_punctuation() {
+        let embedder = HashedNgramEmbedder::new(256, 2, 2);
+        assert_eq!(embedder.embed("民法、刑法"), embedder.embed("民法 刑法"));
+    }
+}
@@ -0,0 +1,966 @@
+//! 法令や判例の参照関係をグラフとして書き出す
+
+use crate::{
+    GRAPH_MAX_DEPTH, LawscapeCoreError, LegalDocument, LegalDocumentDependencies, Precedent,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::str::FromStr;
+
+/// ノードの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphNodeKind {
+    Law,
+    /// 法令の条。`{法令ID}:{条番号}`をIDとする
+    Article,
+    Precedent,
+}
+
+impl GraphNodeKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GraphNodeKind::Law => "law",
+            GraphNodeKind::Article => "article",
+            GraphNodeKind::Precedent => "precedent",
+        }
+    }
+
+    /// `citation::statute_key`で求めた値が法令と条のどちらを表すか
+    pub fn from_statute_key(key: &str) -> Self {
+        if key.contains(':') {
+            GraphNodeKind::Article
+        } else {
+            GraphNodeKind::Law
+        }
+    }
+}
+
+/// 辺の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphEdgeKind {
+    /// 法令名が本文に現れることによる参照
+    Reference,
+    /// 判例が法令の条文を参照している
+    Statute,
+    /// 判例が判例を引用している
+    Citation,
+    /// 政令や府省令が委任元の法令を参照している
+    Delegation,
+}
+
+impl GraphEdgeKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GraphEdgeKind::Reference => "reference",
+            GraphEdgeKind::Statute => "statute",
+            GraphEdgeKind::Citation => "citation",
+            GraphEdgeKind::Delegation => "delegation",
+        }
+    }
+}
+
+impl FromStr for GraphEdgeKind {
+    type Err = LawscapeCoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reference" => Ok(GraphEdgeKind::Reference),
+            "statute" => Ok(GraphEdgeKind::Statute),
+            "citation" => Ok(GraphEdgeKind::Citation),
+            "delegation" => Ok(GraphEdgeKind::Delegation),
+            _ => Err(LawscapeCoreError::InvalidParameter(format!(
+                "unknown edge kind: {s}"
+            ))),
+        }
+    }
+}
+
+/// 辺をたどる向き
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphDirection {
+    /// 参照している文書へ
+    Outgoing,
+    /// 参照されている文書へ
+    Incoming,
+    /// 両方
+    #[default]
+    Both,
+}
+
+impl FromStr for GraphDirection {
+    type Err = LawscapeCoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "out" | "outgoing" => Ok(GraphDirection::Outgoing),
+            "in" | "incoming" => Ok(GraphDirection::Incoming),
+            "both" => Ok(GraphDirection::Both),
+            _ => Err(LawscapeCoreError::InvalidParameter(format!(
+                "unknown direction: {s}"
+            ))),
+        }
+    }
+}
+
+impl GraphDirection {
+    pub fn is_outgoing(&self) -> bool {
+        matches!(self, GraphDirection::Outgoing | GraphDirection::Both)
+    }
+
+    pub fn is_incoming(&self) -> bool {
+        matches!(self, GraphDirection::Incoming | GraphDirection::Both)
+    }
+}
+
+/// グラフをたどる際の条件
+#[derive(Debug, Clone)]
+pub struct GraphTraversal {
+    /// たどる辺の数の上限
+    pub depth: usize,
+    pub direction: GraphDirection,
+    /// たどる辺の種類。空の場合は全ての種類をたどる
+    pub edge_kinds: Vec<GraphEdgeKind>,
+}
+
+impl Default for GraphTraversal {
+    fn default() -> Self {
+        Self {
+            depth: 1,
+            direction: GraphDirection::default(),
+            edge_kinds: Vec::new(),
+        }
+    }
+}
+
+impl GraphTraversal {
+    pub fn allows(&self, kind: GraphEdgeKind) -> bool {
+        self.edge_kinds.is_empty() || self.edge_kinds.contains(&kind)
+    }
+}
+
+/// 二つの文書を結ぶ経路
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GraphPath {
+    /// 始点から終点までのノード
+    pub nodes: Vec<GraphNode>,
+    /// `nodes`の隣り合うノードを結ぶ辺。向きは参照する文書から参照される文書への向き
+    pub edges: Vec<GraphEdge>,
+}
+
+/// ノード。法令は法令ID、判例は判決全体のIDで表す
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct GraphNode {
+    pub id: String,
+    /// 法令名や事件名
+    pub label: String,
+    pub kind: GraphNodeKind,
+}
+
+/// 参照する文書から参照される文書への辺
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub kind: GraphEdgeKind,
+}
+
+/// 法令や判例の参照関係のグラフ
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LegalGraph {
+    /// IDの順に並ぶ
+    pub nodes: Vec<GraphNode>,
+    /// 参照する文書のIDの順に並ぶ
+    pub edges: Vec<GraphEdge>,
+}
+
+/// グラフをたどる際に見つかった辺
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct NeighborEdge {
+    /// たどる元の文書のID
+    pub from: String,
+    pub edge: GraphEdge,
+    /// 辺の反対側の文書のID
+    pub other: String,
+    pub other_kind: GraphNodeKind,
+}
+
+impl NeighborEdge {
+    /// `from`から`other`への辺
+    pub(crate) fn outgoing(
+        from: &str,
+        other: &str,
+        kind: GraphEdgeKind,
+        other_kind: GraphNodeKind,
+    ) -> Self {
+        Self {
+            from: from.to_string(),
+            edge: GraphEdge {
+                source: from.to_string(),
+                target: other.to_string(),
+                kind,
+            },
+            other: other.to_string(),
+            other_kind,
+        }
+    }
+
+    /// `other`から`from`への辺
+    pub(crate) fn incoming(
+        from: &str,
+        other: &str,
+        kind: GraphEdgeKind,
+        other_kind: GraphNodeKind,
+    ) -> Self {
+        Self {
+            from: from.to_string(),
+            edge: GraphEdge {
+                source: other.to_string(),
+                target: from.to_string(),
+                kind,
+            },
+            other: other.to_string(),
+            other_kind,
+        }
+    }
+}
+
+/// グラフをたどる際に文書と辺を取得するもの
+pub(crate) trait GraphSource {
+    /// 文書につながる辺をまとめて取得する
+    async fn neighbor_edges(
+        &self,
+        nodes: &[GraphNode],
+        traversal: &GraphTraversal,
+    ) -> Result<Vec<NeighborEdge>, LawscapeCoreError>;
+
+    /// IDと種類から文書のノードをまとめて取得する。登録されていない文書は含めない
+    async fn nodes(
+        &self,
+        ids: &[(String, GraphNodeKind)],
+    ) -> Result<Vec<GraphNode>, LawscapeCoreError>;
+}
+
+/// 幅優先でグラフをたどる途中の状態
+struct Exploration {
+    /// 訪れた文書
+    nodes: HashMap<String, GraphNode>,
+    /// 訪れない文書。登録されていない文書と、上限を超えたために訪れなかった文書
+    excluded: HashSet<String>,
+    max_nodes: usize,
+}
+
+impl Exploration {
+    fn new(start: GraphNode, max_nodes: usize) -> Self {
+        Self {
+            nodes: HashMap::from([(start.id.clone(), start)]),
+            excluded: HashSet::new(),
+            max_nodes,
+        }
+    }
+
+    /// `frontier`の文書から辺を一つたどる。両端が訪れた文書である辺と、新しく訪れた文書を返す
+    async fn step<S: GraphSource>(
+        &mut self,
+        source: &S,
+        frontier: &[GraphNode],
+        traversal: &GraphTraversal,
+    ) -> Result<(Vec<NeighborEdge>, Vec<GraphNode>), LawscapeCoreError> {
+        let mut edges = source.neighbor_edges(frontier, traversal).await?;
+        // 条件に合わない辺と自分自身への辺はたどらない
+        edges.retain(|e| {
+            let is_allowed_direction = if e.edge.source == e.from {
+                traversal.direction.is_outgoing()
+            } else {
+                traversal.direction.is_incoming()
+            };
+            is_allowed_direction && traversal.allows(e.edge.kind) && e.from != e.other
+        });
+        edges.sort();
+        edges.dedup();
+        let mut candidates: Vec<(String, GraphNodeKind)> = Vec::new();
+        for edge in edges.iter() {
+            if !self.nodes.contains_key(&edge.other)
+                && !self.excluded.contains(&edge.other)
+                && !candidates.iter().any(|(id, _)| id == &edge.other)
+            {
+                candidates.push((edge.other.clone(), edge.other_kind));
+            }
+        }
+        let mut found = source
+            .nodes(&candidates)
+            .await?
+            .into_iter()
+            .map(|node| (node.id.clone(), node))
+            .collect::<HashMap<String, GraphNode>>();
+        let mut new_nodes = Vec::new();
+        for (id, _) in candidates {
+            match found.remove(&id) {
+                Some(node) if self.nodes.len() < self.max_nodes => {
+                    self.nodes.insert(id, node.clone());
+                    new_nodes.push(node);
+                }
+                _ => {
+                    self.excluded.insert(id);
+                }
+            }
+        }
+        edges.retain(|edge| self.nodes.contains_key(&edge.other));
+        Ok((edges, new_nodes))
+    }
+}
+
+/// `start`から`traversal`の条件で辺をたどり、周りのグラフを作る。
+/// 訪れる文書は`max_nodes`個までとし、登録されていない文書への辺は含めない。
+pub(crate) async fn explore_neighborhood<S: GraphSource>(
+    source: &S,
+    start: GraphNode,
+    traversal: &GraphTraversal,
+    max_nodes: usize,
+) -> Result<LegalGraph, LawscapeCoreError> {
+    let mut exploration = Exploration::new(start.clone(), max_nodes);
+    let mut builder = LegalGraphBuilder::new();
+    let mut frontier = vec![start];
+    for _ in 0..traversal.depth.min(GRAPH_MAX_DEPTH) {
+        if frontier.is_empty() {
+            break;
+        }
+        let (edges, new_nodes) = exploration.step(source, &frontier, traversal).await?;
+        for NeighborEdge { edge, .. } in edges {
+            builder.add_edge(&edge.source, &edge.target, edge.kind);
+        }
+        frontier = new_nodes;
+    }
+    for node in exploration.nodes.into_values() {
+        builder.add_node(&node.id, &node.label, node.kind);
+    }
+    Ok(builder.build())
+}
+
+/// `start`から`to`への最短の経路を探す。`traversal.depth`は経路に含まれる辺の数の上限となる。
+/// 訪れる文書は`max_nodes`個までとし、登録されていない文書は経路に含めない。
+pub(crate) async fn explore_path<S: GraphSource>(
+    source: &S,
+    start: GraphNode,
+    to: &str,
+    traversal: &GraphTraversal,
+    max_nodes: usize,
+) -> Result<Option<GraphPath>, LawscapeCoreError> {
+    let mut exploration = Exploration::new(start.clone(), max_nodes);
+    // 各文書に至る辺と、その辺の反対側の文書
+    let mut reached: HashMap<String, Option<(GraphEdge, String)>> =
+        HashMap::from([(start.id.clone(), None)]);
+    let mut frontier = vec![start];
+    for _ in 0..traversal.depth.min(GRAPH_MAX_DEPTH) {
+        if reached.contains_key(to) || frontier.is_empty() {
+            break;
+        }
+        let (edges, new_nodes) = exploration.step(source, &frontier, traversal).await?;
+        for NeighborEdge {
+            from, edge, other, ..
+        } in edges
+        {
+            reached.entry(other).or_insert(Some((edge, from)));
+        }
+        frontier = new_nodes;
+    }
+    if !reached.contains_key(to) {
+        return Ok(None);
+    }
+    let mut node_ids = vec![to.to_string()];
+    let mut edges = Vec::new();
+    while let Some(Some((edge, previous))) = node_ids.last().and_then(|id| reached.get(id)) {
+        edges.push(edge.clone());
+        node_ids.push(previous.clone());
+    }
+    node_ids.reverse();
+    edges.reverse();
+    let nodes = node_ids
+        .iter()
+        .filter_map(|id| exploration.nodes.get(id).cloned())
+        .collect();
+    Ok(Some(GraphPath { nodes, edges }))
+}
+
+/// 書き出す形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphFormat {
+    /// ノードと辺の一覧のJSON
+    #[default]
+    Json,
+    /// GraphvizのDOT言語
+    Dot,
+    /// GraphML
+    Graphml,
+}
+
+impl FromStr for GraphFormat {
+    type Err = LawscapeCoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(GraphFormat::Json),
+            "dot" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::Graphml),
+            _ => Err(LawscapeCoreError::InvalidParameter(format!(
+                "unknown graph format: {s}"
+            ))),
+        }
+    }
+}
+
+impl GraphFormat {
+    /// HTTPのレスポンスで使うContent-Type
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            GraphFormat::Json => "application/json",
+            GraphFormat::Dot => "text/vnd.graphviz; charset=utf-8",
+            GraphFormat::Graphml => "application/graphml+xml; charset=utf-8",
+        }
+    }
+}
+
+/// グラフを組み立てるためのもの。ノードと辺の重複を取り除く
+#[derive(Debug, Clone, Default)]
+pub struct LegalGraphBuilder {
+    nodes: BTreeMap<String, GraphNode>,
+    edges: BTreeSet<GraphEdge>,
+}
+
+impl LegalGraphBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ノードを加える。同じIDのノードが既にある場合は何もしない
+    pub fn add_node(&mut self, id: &str, label: &str, kind: GraphNodeKind) {
+        self.nodes
+            .entry(id.to_string())
+            .or_insert_with(|| GraphNode {
+                id: id.to_string(),
+                label: label.to_string(),
+                kind,
+            });
+    }
+
+    pub fn contains_node(&self, id: &str) -> bool {
+        self.nodes.contains_key(id)
+    }
+
+    /// 辺を加える。自分自身への辺は加えない
+    pub fn add_edge(&mut self, source: &str, target: &str, kind: GraphEdgeKind) {
+        if source != target {
+            self.edges.insert(GraphEdge {
+                source: source.to_string(),
+                target: target.to_string(),
+                kind,
+            });
+        }
+    }
+
+    /// 判例が参照している法令の条文と、引用している判例のうち、既にノードがある文書への辺を加える
+    pub fn add_precedent_citations(&mut self, precedent: &Precedent) {
+        let precedent_id = precedent.precedent_id().to_string();
+        self.add_node(
+            &precedent_id,
+            &precedent.info.case_name,
+            GraphNodeKind::Precedent,
+        );
+        for statute in precedent.statutes.iter() {
+            let Some(law_id) = &statute.law_id else {
+                continue;
+            };
+            if self.contains_node(law_id) {
+                self.add_edge(&precedent_id, law_id, GraphEdgeKind::Statute);
+            }
+        }
+        for cited in precedent.cited_precedents.iter() {
+            let Some(cited_id) = &cited.precedent_id else {
+                continue;
+            };
+            if self.contains_node(cited_id) {
+                self.add_edge(&precedent_id, cited_id, GraphEdgeKind::Citation);
+            }
+        }
+    }
+
+    pub fn build(self) -> LegalGraph {
+        LegalGraph {
+            nodes: self.nodes.into_values().collect(),
+            edges: self.edges.into_iter().collect(),
+        }
+    }
+}
+
+/// DOT言語の文字列として書けるようにエスケープする。改行は`\n`にする
+fn escape_dot(str: &str) -> String {
+    str.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("\r\n", "\\n")
+        .replace(['\r', '\n'], "\\n")
+}
+
+/// XMLの属性値や文字列として書けるようにエスケープする
+fn escape_xml(str: &str) -> String {
+    str.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+impl LegalGraph {
+    /// `analyze_search_result_dependencies`の結果からグラフを作る。
+    /// 検索結果に含まれる判例については、検索結果に含まれる法令や判例への参照も辺として加える。
+    pub fn from_dependencies(dependencies: &HashMap<String, LegalDocumentDependencies>) -> Self {
+        let mut builder = LegalGraphBuilder::new();
+        for (id, d) in dependencies.iter() {
+            match d.contents.first().map(|c| &c.document) {
+                Some(LegalDocument::Law(l)) => builder.add_node(id, &l.name, GraphNodeKind::Law),
+                Some(LegalDocument::Precedent(p)) => {
+                    builder.add_node(id, &p.info.case_name, GraphNodeKind::Precedent)
+                }
+                None => {}
+            }
+        }
+        for (id, d) in dependencies.iter() {
+            for parent in d.parents.iter() {
+                let kind = if d.delegating.contains(parent) {
+                    GraphEdgeKind::Delegation
+                } else {
+                    GraphEdgeKind::Reference
+                };
+                builder.add_edge(id, parent, kind);
+            }
+            for content in d.contents.iter() {
+                if let LegalDocument::Precedent(p) = &content.document {
+                    builder.add_precedent_citations(p);
+                }
+            }
+        }
+        builder.build()
+    }
+
+    /// 指定した形式の文字列に書き出す
+    pub fn render(&self, format: GraphFormat) -> Result<String, LawscapeCoreError> {
+        match format {
+            GraphFormat::Json => serde_json::to_string(self)
+                .map_err(|e| LawscapeCoreError::SerializeError(Box::new(e))),
+            GraphFormat::Dot => Ok(self.to_dot()),
+            GraphFormat::Graphml => Ok(self.to_graphml()),
+        }
+    }
+
+    /// GraphvizのDOT言語に書き出す
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec!["digraph lawscape {".to_string()];
+        for node in self.nodes.iter() {
+            lines.push(format!(
+                "  \"{}\" [label=\"{}\", kind=\"{}\"];",
+                escape_dot(&node.id),
+                escape_dot(&node.label),
+                node.kind.name()
+            ));
+        }
+        for edge in self.edges.iter() {
+            lines.push(format!(
+                "  \"{}\" -> \"{}\" [kind=\"{}\"];",
+                escape_dot(&edge.source),
+                escape_dot(&edge.target),
+                edge.kind.name()
+            ));
+        }
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+
+    /// GraphMLに書き出す
+    pub fn to_graphml(&self) -> String {
+        let mut lines = vec![
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#.to_string(),
+            r#"  <key id="label" for="node" attr.name="label" attr.type="string"/>"#.to_string(),
+            r#"  <key id="node_kind" for="node" attr.name="kind" attr.type="string"/>"#.to_string(),
+            r#"  <key id="edge_kind" for="edge" attr.name="kind" attr.type="string"/>"#.to_string(),
+            r#"  <graph id="lawscape" edgedefault="directed">"#.to_string(),
+        ];
+        for node in self.nodes.iter() {
+            lines.push(format!("    <node id=\"{}\">", escape_xml(&node.id)));
+            lines.push(format!(
+                "      <data key=\"label\">{}</data>",
+                escape_xml(&node.label)
+            ));
+            lines.push(format!(
+                "      <data key=\"node_kind\">{}</data>",
+                node.kind.name()
+            ));
+            lines.push("    </node>".to_string());
+        }
+        for edge in self.edges.iter() {
+            lines.push(format!(
+                "    <edge source=\"{}\" target=\"{}\">",
+                escape_xml(&edge.source),
+                escape_xml(&edge.target)
+            ));
+            lines.push(format!(
+                "      <data key=\"edge_kind\">{}</data>",
+                edge.kind.name()
+            ));
+            lines.push("    </edge>".to_string());
+        }
+        lines.push("  </graph>".to_string());
+        lines.push("</graphml>".to_string());
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    /// 待つことの無い`Future`を実行する
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    fn node(id: &str, kind: GraphNodeKind) -> GraphNode {
+        GraphNode {
+            id: id.to_string(),
+            label: format!("{id}の名前"),
+            kind,
+        }
+    }
+
+    fn edge(source: &str, target: &str, kind: GraphEdgeKind) -> GraphEdge {
+        GraphEdge {
+            source: source.to_string(),
+            target: target.to_string(),
+            kind,
+        }
+    }
+
+    /// メモリ上のグラフ。辺の取得の回数を数える
+    struct MemorySource {
+        nodes: Vec<GraphNode>,
+        edges: Vec<GraphEdge>,
+        edge_calls: Cell<usize>,
+    }
+
+    impl MemorySource {
+        /// 判例p1が法令Aを参照し、判例p2を引用している。p2はp3を引用し、
+        /// p1は登録されていない判例missingを、missingはp3を引用している
+        fn new() -> Self {
+            Self {
+                nodes: vec![
+                    node("A", GraphNodeKind::Law),
+                    node("p1", GraphNodeKind::Precedent),
+                    node("p2", GraphNodeKind::Precedent),
+                    node("p3", GraphNodeKind::Precedent),
+                ],
+                edges: vec![
+                    edge("p1", "A", GraphEdgeKind::Statute),
+                    edge("p1", "p2", GraphEdgeKind::Citation),
+                    edge("p1", "missing", GraphEdgeKind::Citation),
+                    edge("missing", "p3", GraphEdgeKind::Citation),
+                    edge("p2", "p3", GraphEdgeKind::Citation),
+                ],
+                edge_calls: Cell::new(0),
+            }
+        }
+
+        fn kind_of(&self, id: &str) -> GraphNodeKind {
+            self.nodes
+                .iter()
+                .find(|n| n.id == id)
+                .map(|n| n.kind)
+                .unwrap_or(GraphNodeKind::Precedent)
+        }
+    }
+
+    impl GraphSource for MemorySource {
+        async fn neighbor_edges(
+            &self,
+            nodes: &[GraphNode],
+            _traversal: &GraphTraversal,
+        ) -> Result<Vec<NeighborEdge>, LawscapeCoreError> {
+            self.edge_calls.set(self.edge_calls.get() + 1);
+            let mut edges = Vec::new();
+            for node in nodes.iter() {
+                for e in self.edges.iter() {
+                    if e.source == node.id {
+                        let kind = self.kind_of(&e.target);
+                        edges.push(NeighborEdge::outgoing(&node.id, &e.target, e.kind, kind));
+                    }
+                    if e.target == node.id {
+                        let kind = self.kind_of(&e.source);
+                        edges.push(NeighborEdge::incoming(&node.id, &e.source, e.kind, kind));
+                    }
+                }
+            }
+            Ok(edges)
+        }
+
+        async fn nodes(
+            &self,
+            ids: &[(String, GraphNodeKind)],
+        ) -> Result<Vec<GraphNode>, LawscapeCoreError> {
+            Ok(self
+                .nodes
+                .iter()
+                .filter(|n| ids.iter().any(|(id, kind)| *id == n.id && *kind == n.kind))
+                .cloned()
+                .collect())
+        }
+    }
+
+    fn traversal(depth: usize, direction: GraphDirection) -> GraphTraversal {
+        GraphTraversal {
+            depth,
+            direction,
+            edge_kinds: Vec::new(),
+        }
+    }
+
+    fn node_ids(graph: &LegalGraph) -> Vec<&str> {
+        graph.nodes.iter().map(|n| n.id.as_str()).collect()
+    }
+
+    #[test]
+    fn neighborhood_drops_unregistered_documents() {
+        let source = MemorySource::new();
+        let start = node("p1", GraphNodeKind::Precedent);
+        let graph = block_on(explore_neighborhood(
+            &source,
+            start,
+            &traversal(1, GraphDirection::Outgoing),
+            100,
+        ))
+        .unwrap();
+        assert_eq!(node_ids(&graph), vec!["A", "p1", "p2"]);
+        assert_eq!(
+            graph.edges,
+            vec![
+                edge("p1", "A", GraphEdgeKind::Statute),
+                edge("p1", "p2", GraphEdgeKind::Citation),
+            ]
+        );
+    }
+
+    #[test]
+    fn neighborhood_fetches_edges_once_per_depth() {
+        let source = MemorySource::new();
+        let start = node("A", GraphNodeKind::Law);
+        let graph = block_on(explore_neighborhood(
+            &source,
+            start,
+            &traversal(3, GraphDirection::Both),
+            100,
+        ))
+        .unwrap();
+        assert_eq!(node_ids(&graph), vec!["A", "p1", "p2", "p3"]);
+        assert_eq!(graph.edges.len(), 3);
+        assert_eq!(source.edge_calls.get(), 3);
+    }
+
+    #[test]
+    fn neighborhood_follows_direction_and_edge_kinds() {
+        let source = MemorySource::new();
+        let start = node("p2", GraphNodeKind::Precedent);
+        let graph = block_on(explore_neighborhood(
+            &source,
+            start.clone(),
+            &traversal(2, GraphDirection::Incoming),
+            100,
+        ))
+        .unwrap();
+        assert_eq!(node_ids(&graph), vec!["p1", "p2"]);
+        let graph = block_on(explore_neighborhood(
+            &source,
+            start,
+            &GraphTraversal {
+                edge_kinds: vec![GraphEdgeKind::Statute],
+                ..traversal(2, GraphDirection::Both)
+            },
+            100,
+        ))
+        .unwrap();
+        assert_eq!(node_ids(&graph), vec!["p2"]);
+        assert!(graph.edges.is_empty());
+    }
+
+    #[test]
+    fn neighborhood_is_limited_by_max_nodes() {
+        let source = MemorySource::new();
+        let start = node("p1", GraphNodeKind::Precedent);
+        let graph = block_on(explore_neighborhood(
+            &source,
+            start,
+            &traversal(2, GraphDirection::Outgoing),
+            2,
+        ))
+        .unwrap();
+        assert_eq!(node_ids(&graph), vec!["A", "p1"]);
+        assert_eq!(graph.edges, vec![edge("p1", "A", GraphEdgeKind::Statute)]);
+    }
+
+    #[test]
+    fn path_through_registered_documents() {
+        let source = MemorySource::new();
+        let start = node("p1", GraphNodeKind::Precedent);
+        let path = block_on(explore_path(
+            &source,
+            start.clone(),
+            "p3",
+            &traversal(2, GraphDirection::Outgoing),
+            100,
+        ))
+        .unwrap()
+        .unwrap();
+        let ids = path.nodes.iter().map(|n| n.id.as_str()).collect::<Vec<_>>();
+        // 登録されていない判例missingを通る経路は使わない
+        assert_eq!(ids, vec!["p1", "p2", "p3"]);
+        assert_eq!(
+            path.edges,
+            vec![
+                edge("p1", "p2", GraphEdgeKind::Citation),
+                edge("p2", "p3", GraphEdgeKind::Citation),
+            ]
+        );
+        let path = block_on(explore_path(
+            &source,
+            start,
+            "p3",
+            &traversal(1, GraphDirection::Outgoing),
+            100,
+        ))
+        .unwrap();
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn path_against_edge_direction() {
+        let source = MemorySource::new();
+        let start = node("A", GraphNodeKind::Law);
+        let path = block_on(explore_path(
+            &source,
+            start.clone(),
+            "p2",
+            &traversal(2, GraphDirection::Incoming),
+            100,
+        ))
+        .unwrap();
+        assert_eq!(path, None);
+        let path = block_on(explore_path(
+            &source,
+            start.clone(),
+            "p2",
+            &traversal(2, GraphDirection::Both),
+            100,
+        ))
+        .unwrap()
+        .unwrap();
+        assert_eq!(path.nodes.len(), 3);
+        assert_eq!(path.edges[0], edge("p1", "A", GraphEdgeKind::Statute));
+        let path = block_on(explore_path(
+            &source,
+            start,
+            "A",
+            &traversal(1, GraphDirection::Both),
+            100,
+        ))
+        .unwrap()
+        .unwrap();
+        assert_eq!(path.nodes.len(), 1);
+        assert!(path.edges.is_empty());
+    }
+
+    fn graph() -> LegalGraph {
+        LegalGraph {
+            nodes: vec![
+                GraphNode {
+                    id: "129AC0000000089".to_string(),
+                    label: "民法".to_string(),
+                    kind: GraphNodeKind::Law,
+                },
+                GraphNode {
+                    id: "p1".to_string(),
+                    label: "損害賠償\"請求\"\n事件".to_string(),
+                    kind: GraphNodeKind::Precedent,
+                },
+            ],
+            edges: vec![GraphEdge {
+                source: "p1".to_string(),
+                target: "129AC0000000089".to_string(),
+                kind: GraphEdgeKind::Statute,
+            }],
+        }
+    }
+
+    #[test]
+    fn escape_dot_string() {
+        assert_eq!(escape_dot(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(
+            escape_dot("一行目\n二行目\r\n三行目"),
+            r"一行目\n二行目\n三行目"
+        );
+    }
+
+    #[test]
+    fn escape_xml_string() {
+        assert_eq!(
+            escape_xml(r#"<a href="x">&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&apos;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn render_dot() {
+        assert_eq!(
+            graph().to_dot(),
+            [
+                "digraph lawscape {",
+                r#"  "129AC0000000089" [label="民法", kind="law"];"#,
+                r#"  "p1" [label="損害賠償\"請求\"\n事件", kind="precedent"];"#,
+                r#"  "p1" -> "129AC0000000089" [kind="statute"];"#,
+                "}",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn render_graphml() {
+        let graphml = graph().to_graphml();
+        assert!(graphml.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        assert!(graphml.contains(
+            "    <node id=\"p1\">\n      <data key=\"label\">損害賠償&quot;請求&quot;\n事件</data>\n      <data key=\"node_kind\">precedent</data>\n    </node>"
+        ));
+        assert!(graphml.contains(
+            "    <edge source=\"p1\" target=\"129AC0000000089\">\n      <data key=\"edge_kind\">statute</data>\n    </edge>"
+        ));
+        assert!(graphml.ends_with("  </graph>\n</graphml>"));
+    }
+
+    #[test]
+    fn builder_removes_duplicates_and_self_loops() {
+        let mut builder = LegalGraphBuilder::new();
+        builder.add_node("p1", "事件", GraphNodeKind::Precedent);
+        builder.add_node("p1", "別の名前", GraphNodeKind::Precedent);
+        builder.add_edge("p1", "p2", GraphEdgeKind::Citation);
+        builder.add_edge("p1", "p2", GraphEdgeKind::Citation);
+        builder.add_edge("p1", "p1", GraphEdgeKind::Citation);
+        let graph = builder.build();
+        assert_eq!(graph.nodes.len(), 1);
+        assert_eq!(graph.nodes[0].label, "事件");
+        assert_eq!(graph.edges.len(), 1);
+    }
+}
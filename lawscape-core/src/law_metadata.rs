@@ -0,0 +1,318 @@
+//! 法令XMLから法令番号・法令の種類・公布日や、条文の見出し・編章節などの情報を取り出す
+
+use crate::era_offset;
+use japanese_law_xml_schema::article::{
+    Article, Chapter, ChapterContents, Division, Part, Section, SectionContents, Subsection,
+    SubsectionContents,
+};
+use japanese_law_xml_schema::law::{self as xml_law, Law as LawXml, MainProvisionContents};
+use japanese_law_xml_schema::suppl_provision::SupplProvisionChildrenElement;
+use japanese_law_xml_schema::text::Text;
+use jplaw_data_types::law::{Date, Era};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// 法令の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LawType {
+    /// 憲法
+    Constitution,
+    /// 法律
+    Act,
+    /// 政令
+    CabinetOrder,
+    /// 勅令
+    ImperialOrder,
+    /// 府省令
+    MinisterialOrdinance,
+    /// 規則
+    Rule,
+    /// その他
+    Misc,
+}
+
+impl LawType {
+    /// 法令XMLの`LawType`属性の値から求める
+    fn from_xml(law_type: &xml_law::LawType) -> Self {
+        match law_type {
+            xml_law::LawType::Constitution => LawType::Constitution,
+            xml_law::LawType::Act => LawType::Act,
+            xml_law::LawType::CabinetOrder => LawType::CabinetOrder,
+            xml_law::LawType::ImperialOrder => LawType::ImperialOrder,
+            xml_law::LawType::MinisterialOrdinance => LawType::MinisterialOrdinance,
+            xml_law::LawType::Rule => LawType::Rule,
+            xml_law::LawType::Misc => LawType::Misc,
+        }
+    }
+
+    /// 登録されている文書の`law_type`属性の値
+    pub fn name(&self) -> &'static str {
+        match self {
+            LawType::Constitution => "constitution",
+            LawType::Act => "act",
+            LawType::CabinetOrder => "cabinet_order",
+            LawType::ImperialOrder => "imperial_order",
+            LawType::MinisterialOrdinance => "ministerial_ordinance",
+            LawType::Rule => "rule",
+            LawType::Misc => "misc",
+        }
+    }
+}
+
+/// 法令全体の情報
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LawMetadata {
+    /// 法令番号。「明治二十九年法律第八十九号」など
+    pub law_num: Option<String>,
+    pub law_type: Option<LawType>,
+    /// 公布日の西暦での年
+    pub promulgation_year: Option<usize>,
+    /// 公布日。月日が分からない場合は`None`
+    pub promulgation_date: Option<Date>,
+}
+
+impl LawMetadata {
+    /// 解析済みの法令XMLから求める
+    pub fn from_xml(law: &LawXml) -> Self {
+        let (promulgation_year, promulgation_date) = promulgation(
+            era_from_xml(&law.era),
+            law.year,
+            law.promulgate_month,
+            law.promulgate_day,
+        );
+        let law_num = law.law_num.trim();
+        LawMetadata {
+            law_num: (!law_num.is_empty()).then(|| law_num.to_string()),
+            law_type: Some(LawType::from_xml(&law.law_type)),
+            promulgation_year,
+            promulgation_date,
+        }
+    }
+}
+
+/// 条文ごとの情報
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArticleMetadata {
+    /// 法令XMLの`Num`属性の値
+    pub num: String,
+    /// 条文の見出し。「（不法行為による損害賠償）」など
+    pub caption: Option<String>,
+    /// 条文の属する編・章・節・款・目の見出しを外側から順に並べたもの
+    pub chapter_path: Vec<String>,
+    /// 附則の条文の場合は附則の見出し
+    pub suppl_provision: Option<String>,
+}
+
+fn era_from_xml(era: &xml_law::Era) -> Era {
+    match era {
+        xml_law::Era::Meiji => Era::Meiji,
+        xml_law::Era::Taisho => Era::Taisho,
+        xml_law::Era::Showa => Era::Showa,
+        xml_law::Era::Heisei => Era::Heisei,
+        xml_law::Era::Reiwa => Era::Reiwa,
+    }
+}
+
+/// 元号での公布年と月日から、西暦での公布年と公布日を求める
+fn promulgation(
+    era: Era,
+    year: usize,
+    month: Option<usize>,
+    day: Option<usize>,
+) -> (Option<usize>, Option<Date>) {
+    let year = era_offset(&era) + year;
+    let date = month
+        .zip(day)
+        .map(|(month, day)| Date::gen_from_ad(year, month, day));
+    (Some(year), date)
+}
+
+/// 空でない見出しを外側の見出しの後ろに加える
+fn push_title(chapter_path: &[String], title: &Text) -> Vec<String> {
+    let title = title.to_string();
+    let title = title.trim();
+    let mut chapter_path = chapter_path.to_vec();
+    if !title.is_empty() {
+        chapter_path.push(title.to_string());
+    }
+    chapter_path
+}
+
+/// 法令XMLの条文を出現順に集める
+#[derive(Debug, Default)]
+struct ArticleMetadataCollector {
+    article_list: Vec<ArticleMetadata>,
+    /// 附則を読んでいる間は附則の見出し
+    suppl_provision: Option<String>,
+}
+
+impl ArticleMetadataCollector {
+    fn article(&mut self, article: &Article, chapter_path: &[String]) {
+        let caption = article
+            .caption
+            .as_ref()
+            .map(|caption| caption.text.to_string().trim().to_string())
+            .filter(|caption| !caption.is_empty());
+        self.article_list.push(ArticleMetadata {
+            num: article.num.num_str(),
+            caption,
+            chapter_path: chapter_path.to_vec(),
+            suppl_provision: self.suppl_provision.clone(),
+        });
+    }
+
+    fn part(&mut self, part: &Part, chapter_path: &[String]) {
+        let chapter_path = push_title(chapter_path, &part.part_title);
+        for chapter in part.children.iter() {
+            self.chapter(chapter, &chapter_path);
+        }
+    }
+
+    fn chapter(&mut self, chapter: &Chapter, chapter_path: &[String]) {
+        let chapter_path = push_title(chapter_path, &chapter.chapter_title);
+        for child in chapter.children.iter() {
+            match child {
+                ChapterContents::Article(article) => self.article(article, &chapter_path),
+                ChapterContents::Section(section) => self.section(section, &chapter_path),
+            }
+        }
+    }
+
+    fn section(&mut self, section: &Section, chapter_path: &[String]) {
+        let chapter_path = push_title(chapter_path, &section.section_title);
+        for child in section.children.iter() {
+            match child {
+                SectionContents::Article(article) => self.article(article, &chapter_path),
+                SectionContents::Subsection(subsection) => {
+                    self.subsection(subsection, &chapter_path)
+                }
+                SectionContents::Division(division) => self.division(division, &chapter_path),
+            }
+        }
+    }
+
+    fn subsection(&mut self, subsection: &Subsection, chapter_path: &[String]) {
+        let chapter_path = push_title(chapter_path, &subsection.subsection_title);
+        for child in subsection.children.iter() {
+            match child {
+                SubsectionContents::Article(article) => self.article(article, &chapter_path),
+                SubsectionContents::Division(division) => self.division(division, &chapter_path),
+            }
+        }
+    }
+
+    fn division(&mut self, division: &Division, chapter_path: &[String]) {
+        let chapter_path = push_title(chapter_path, &division.division_title);
+        for article in division.children.iter() {
+            self.article(article, &chapter_path);
+        }
+    }
+}
+
+/// 解析済みの法令XMLから、法令全体の情報と条文ごとの情報を出現順に取り出す
+pub fn parse_law_metadata(law: &LawXml) -> (LawMetadata, Vec<ArticleMetadata>) {
+    let mut collector = ArticleMetadataCollector::default();
+    for child in law.law_body.main_provision.children.iter() {
+        match child {
+            MainProvisionContents::Part(part) => collector.part(part, &[]),
+            MainProvisionContents::Chapter(chapter) => collector.chapter(chapter, &[]),
+            MainProvisionContents::Section(section) => collector.section(section, &[]),
+            MainProvisionContents::Article(article) => collector.article(article, &[]),
+            MainProvisionContents::Paragraph(_) => (),
+        }
+    }
+    // 附則の中では本則の編・章などは引き継がない
+    for suppl_provision in law.law_body.suppl_provision.iter() {
+        let label = suppl_provision.label.to_string();
+        collector.suppl_provision = Some(label.trim().to_string());
+        for child in suppl_provision.children.iter() {
+            match child {
+                SupplProvisionChildrenElement::Chapter(chapter) => collector.chapter(chapter, &[]),
+                SupplProvisionChildrenElement::Article(article) => collector.article(article, &[]),
+                SupplProvisionChildrenElement::Paragraph(_) => (),
+            }
+        }
+    }
+    (LawMetadata::from_xml(law), collector.article_list)
+}
+
+/// 条番号ごとに条文の情報を出現順に取り出せるようにしたもの
+#[derive(Debug, Clone, Default)]
+pub struct ArticleMetadataTable {
+    table: HashMap<String, VecDeque<ArticleMetadata>>,
+}
+
+impl ArticleMetadataTable {
+    pub fn new(article_list: Vec<ArticleMetadata>) -> Self {
+        let mut table: HashMap<String, VecDeque<ArticleMetadata>> = HashMap::new();
+        for article in article_list {
+            table
+                .entry(article.num.clone())
+                .or_default()
+                .push_back(article);
+        }
+        Self { table }
+    }
+
+    /// 条番号が一致する条文の情報のうち、まだ取り出していない最初のものを取り出す。
+    /// 本則と附則で同じ条番号がある場合も出現順に対応付けられる。
+    pub fn take(&mut self, num: &str) -> Option<ArticleMetadata> {
+        self.table.get_mut(num).and_then(|list| list.pop_front())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(num: &str, caption: &str) -> ArticleMetadata {
+        ArticleMetadata {
+            num: num.to_string(),
+            caption: Some(caption.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn law_type_from_xml() {
+        assert_eq!(LawType::from_xml(&xml_law::LawType::Act), LawType::Act);
+        assert_eq!(
+            LawType::from_xml(&xml_law::LawType::MinisterialOrdinance),
+            LawType::MinisterialOrdinance
+        );
+        assert_eq!(
+            LawType::MinisterialOrdinance.name(),
+            "ministerial_ordinance"
+        );
+    }
+
+    #[test]
+    fn promulgation_with_date() {
+        let (year, date) = promulgation(Era::Meiji, 29, Some(4), Some(27));
+        assert_eq!(year, Some(1896));
+        assert_eq!(date, Some(Date::gen_from_ad(1896, 4, 27)));
+        assert_eq!(era_offset(&era_from_xml(&xml_law::Era::Reiwa)), 2018);
+    }
+
+    #[test]
+    fn promulgation_without_day() {
+        let (year, date) = promulgation(Era::Heisei, 15, Some(5), None);
+        assert_eq!(year, Some(2003));
+        assert_eq!(date, None);
+    }
+
+    #[test]
+    fn take_articles_in_order() {
+        let mut table = ArticleMetadataTable::new(vec![
+            article("1", "（趣旨）"),
+            article("2", "（定義）"),
+            article("1", "（施行期日）"),
+        ]);
+        assert_eq!(table.take("1").unwrap().caption.unwrap(), "（趣旨）");
+        assert_eq!(table.take("1").unwrap().caption.unwrap(), "（施行期日）");
+        assert_eq!(table.take("1"), None);
+        assert_eq!(table.take("2").unwrap().caption.unwrap(), "（定義）");
+        assert_eq!(table.take("3"), None);
+    }
+}
@@ -1,11 +1,45 @@
 #![recursion_limit = "256"]
 
+pub mod citation;
+pub mod definition;
+pub mod delegation;
+pub mod embedding;
+pub mod graph;
+pub mod law_metadata;
+pub mod patch;
+pub mod precedent;
+pub mod query;
+pub mod similar;
+pub mod structure;
+
+use citation::{Citation, LawNameTable, PrecedentReference, StatuteReference};
+use definition::{DefinedTerm, TermDefinition};
+use delegation::DelegationNode;
+use embedding::Embedder;
+use graph::{
+    GraphEdgeKind, GraphNode, GraphNodeKind, GraphPath, GraphSource, GraphTraversal, LegalGraph,
+    NeighborEdge,
+};
 use jplaw_data_types::article::ArticleIndex;
-use jplaw_data_types::law::LawId;
+use jplaw_data_types::law::{Date, Era, LawId};
 use jplaw_data_types::precedent::PrecedentInfo;
+use law_metadata::LawType;
 use meilisearch_sdk::client::Client;
+use meilisearch_sdk::documents::DocumentsQuery;
+use meilisearch_sdk::errors::{Error as MeilisearchError, ErrorCode};
+use meilisearch_sdk::search::{MatchingStrategies, SearchResult, SearchResults, Selectors};
+use meilisearch_sdk::settings::{
+    Embedder as MeilisearchEmbedder, EmbedderSource, FacetingSettings, PaginationSetting, Settings,
+};
+use precedent::{CitedPrecedent, PrecedentSectionKind, PrecedentStatute, PrecedentTable};
+use query::CompiledQuery;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use structure::{ArticleItem, ArticleParagraph};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -16,22 +50,352 @@ pub enum LawscapeCoreError {
     MeilisearchIndexError(Box<dyn std::error::Error + Send + Sync>),
     #[error("meilisearch client error; {0}")]
     MeilisearchSearchError(Box<dyn std::error::Error + Send + Sync>),
+    #[error("meilisearch document error; {0}")]
+    MeilisearchDocumentError(Box<dyn std::error::Error + Send + Sync>),
+    #[error("meilisearch settings error; {0}")]
+    MeilisearchSettingsError(Box<dyn std::error::Error + Send + Sync>),
+    #[error("query syntax error; {0}")]
+    QuerySyntaxError(String),
+    #[error("invalid parameter; {0}")]
+    InvalidParameter(String),
+    #[error("serialize error; {0}")]
+    SerializeError(Box<dyn std::error::Error + Send + Sync>),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Hash, PartialEq, Eq)]
 pub struct Law {
+    /// 条文ごとのID
     pub id: String,
     pub law_id: LawId,
     pub name: String,
     pub index: ArticleIndex,
     pub text: String,
+    /// 法令内での条文の出現順
+    #[serde(default)]
+    pub position: usize,
+    /// 項と号の構造
+    #[serde(default)]
+    pub paragraphs: Vec<ArticleParagraph>,
+    /// 項ごとに分けて登録した場合の項番号
+    #[serde(default)]
+    pub paragraph: Option<String>,
+    /// 条文の見出し。「（不法行為による損害賠償）」など
+    #[serde(default)]
+    pub caption: Option<String>,
+    /// 条文の属する編・章・節・款・目の見出しを外側から順に並べたもの
+    #[serde(default)]
+    pub chapter_path: Vec<String>,
+    /// 附則の条文の場合は附則の見出し
+    #[serde(default)]
+    pub suppl_provision: Option<String>,
+    /// 法令番号
+    #[serde(default)]
+    pub law_num: Option<String>,
+    /// 法令の種類
+    #[serde(default)]
+    pub law_type: Option<LawType>,
+    /// 公布日
+    #[serde(default)]
+    pub promulgation_date: Option<Date>,
+    /// 公布日の西暦での年。絞り込みに使う
+    #[serde(default)]
+    pub promulgation_year: Option<usize>,
+    /// 施行状況
+    #[serde(default)]
+    pub status: LawStatus,
+    /// この版の施行日
+    #[serde(default)]
+    pub effective_date: Option<Date>,
+    /// この版の施行日を`yyyymmdd`の形の数にしたもの。時点を指定した絞り込みに使う
+    #[serde(default)]
+    pub date_number: Option<usize>,
+    /// 参照している他の法令と条文。`citation::statute_key`で求める
+    #[serde(default)]
+    pub reference_keys: Vec<String>,
+    /// 委任元の法令の法令ID。「〇〇法施行令」の場合は「〇〇法」の法令ID
+    #[serde(default)]
+    pub delegated_by: Option<String>,
+    /// 条文中の「政令で定める」などの文言から求めた委任先の法令の種類
+    #[serde(default)]
+    pub delegated_law_types: Vec<LawType>,
+    /// 条文で定義されている用語
+    #[serde(default)]
+    pub definitions: Vec<TermDefinition>,
+    /// 定義されている用語の一覧。絞り込みに使う
+    #[serde(default)]
+    pub defined_terms: Vec<String>,
+}
+
+/// 法令の施行状況
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LawStatus {
+    /// 施行されている
+    #[default]
+    InForce,
+    /// 廃止されている
+    Repealed,
+    /// 公布されているが、まだ施行されていない
+    NotYetEffective,
+}
+
+impl LawStatus {
+    /// 登録されている文書の`status`属性の値
+    fn name(&self) -> &'static str {
+        match self {
+            LawStatus::InForce => "in_force",
+            LawStatus::Repealed => "repealed",
+            LawStatus::NotYetEffective => "not_yet_effective",
+        }
+    }
+
+    /// 施行されている版の条文から施行状況を判定する。
+    /// 法令XMLには廃止されたことを示す属性が無いため、廃止の際に全ての条文が「削除」となることを使い、
+    /// 条文が無い場合や全ての条文が「削除」か空となっている場合に廃止されたものとみなす。
+    /// 一部の条文だけが「削除」の場合は施行されているものとする。
+    pub fn detect(law_list: &[Law]) -> Self {
+        Self::detect_from_texts(law_list.iter().map(|law| law.text.as_str()))
+    }
+
+    fn detect_from_texts<'a>(texts: impl IntoIterator<Item = &'a str>) -> Self {
+        let is_repealed = texts.into_iter().all(|text| {
+            let text = text.trim();
+            text.is_empty() || text == "削除"
+        });
+        if is_repealed {
+            LawStatus::Repealed
+        } else {
+            LawStatus::InForce
+        }
+    }
+}
+
+impl Law {
+    /// 条文を項ごとの文書に分ける。項が一つだけの場合はそのまま返す
+    pub fn into_paragraphs(self) -> Vec<Law> {
+        if self.paragraphs.len() <= 1 {
+            return vec![self];
+        }
+        self.paragraphs
+            .iter()
+            .map(|paragraph| {
+                let definitions = self
+                    .definitions
+                    .iter()
+                    .filter(|d| d.paragraph == paragraph.num)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                Law {
+                    id: format!("{}_{}", self.id, paragraph.num),
+                    text: paragraph.full_text(),
+                    paragraphs: vec![paragraph.clone()],
+                    paragraph: Some(paragraph.num.clone()),
+                    defined_terms: definition::defined_terms(&definitions),
+                    definitions,
+                    ..self.clone()
+                }
+            })
+            .collect()
+    }
+
+    /// 項番号と号番号を指定して該当する箇所を探す
+    pub fn locate(&self, paragraph: usize, item: Option<usize>) -> Option<LocatedProvision> {
+        let (paragraph, item) = structure::locate_provision(&self.paragraphs, paragraph, item)?;
+        Some(LocatedProvision {
+            document_id: self.id.clone(),
+            paragraph: paragraph.clone(),
+            item: item.cloned(),
+        })
+    }
+}
+
+/// 引用で指定された項や号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocatedProvision {
+    /// 項を含む文書のID
+    pub document_id: String,
+    pub paragraph: ArticleParagraph,
+    /// 号が指定された場合の号
+    pub item: Option<ArticleItem>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Hash, PartialEq, Eq)]
 pub struct Precedent {
     pub id: String,
+    /// 判決全体のID。区分ごとに分けて登録した場合も全ての区分で同じ値になり、検索で判決ごとに一件にまとめるのに使う
+    #[serde(default)]
+    pub precedent_id: String,
     pub info: PrecedentInfo,
     pub text: String,
+    /// 判決日の西暦での年。絞り込みと集計に使う
+    #[serde(default)]
+    pub year: Option<usize>,
+    /// 判決日を`yyyymmdd`の形の数にしたもの。並び替えに使う
+    #[serde(default)]
+    pub date_number: Option<usize>,
+    /// 裁判所の審級。大きいほど上級の裁判所
+    #[serde(default)]
+    pub court_rank: usize,
+    /// 判決文を区分ごとに分けて登録した場合の区分の情報
+    #[serde(default)]
+    pub section: Option<PrecedentSection>,
+    /// 参照している法令の条文
+    #[serde(default)]
+    pub statutes: Vec<PrecedentStatute>,
+    /// 参照している法令の条文で絞り込むための値。`citation::statute_key`で求める
+    #[serde(default)]
+    pub statute_keys: Vec<String>,
+    /// 引用している判例
+    #[serde(default)]
+    pub cited_precedents: Vec<CitedPrecedent>,
+    /// 引用している判例のうち、登録されている判例と対応付けられたもののID
+    #[serde(default)]
+    pub cited_precedent_ids: Vec<String>,
+}
+
+/// 判決文の区分の情報
+#[derive(Debug, Clone, Deserialize, Serialize, Hash, PartialEq, Eq)]
+pub struct PrecedentSection {
+    /// 判決全体のID
+    pub precedent_id: String,
+    pub kind: PrecedentSectionKind,
+    /// 見出し
+    pub title: String,
+    /// 判決文の中での区分の出現順
+    pub position: usize,
+}
+
+impl Precedent {
+    /// 判例の情報から絞り込みや並び替えに使う値を求めて生成する
+    pub fn new(id: String, info: PrecedentInfo, text: String) -> Self {
+        let (year, month, day) = date_to_ad(&info.date);
+        let court_rank = court_rank(&info.court_name);
+        Self {
+            precedent_id: id.clone(),
+            id,
+            info,
+            text,
+            year: Some(year),
+            date_number: Some(year * 10000 + month * 100 + day),
+            court_rank,
+            section: None,
+            statutes: Vec::new(),
+            statute_keys: Vec::new(),
+            cited_precedents: Vec::new(),
+            cited_precedent_ids: Vec::new(),
+        }
+    }
+
+    /// 参照している法令の条文を設定する
+    pub fn with_statutes(mut self, statutes: Vec<PrecedentStatute>) -> Self {
+        let mut statute_keys = statutes
+            .iter()
+            .flat_map(|s| s.keys())
+            .collect::<Vec<String>>();
+        statute_keys.sort();
+        statute_keys.dedup();
+        self.statutes = statutes;
+        self.statute_keys = statute_keys;
+        self
+    }
+
+    /// 引用している判例を設定する
+    pub fn with_cited_precedents(mut self, cited_precedents: Vec<CitedPrecedent>) -> Self {
+        let mut cited_precedent_ids = cited_precedents
+            .iter()
+            .filter_map(|c| c.precedent_id.clone())
+            .collect::<Vec<String>>();
+        cited_precedent_ids.sort();
+        cited_precedent_ids.dedup();
+        self.cited_precedents = cited_precedents;
+        self.cited_precedent_ids = cited_precedent_ids;
+        self
+    }
+
+    /// 判決全体のID。区分ごとに分けて登録した場合も元の判決のIDを返す
+    pub fn precedent_id(&self) -> &str {
+        if !self.precedent_id.is_empty() {
+            return &self.precedent_id;
+        }
+        match &self.section {
+            Some(section) => &section.precedent_id,
+            None => &self.id,
+        }
+    }
+
+    /// 判決文を主文・理由などの区分ごとの文書に分ける。区分が見つからない場合はそのまま返す
+    pub fn into_sections(self) -> Vec<Precedent> {
+        let sections = precedent::split_precedent_sections(&self.text);
+        if sections.is_empty() {
+            return vec![self];
+        }
+        sections
+            .into_iter()
+            .enumerate()
+            .map(|(position, section)| Precedent {
+                id: format!("{}_{position}", self.id),
+                precedent_id: self.id.clone(),
+                info: self.info.clone(),
+                text: section.text,
+                year: self.year,
+                date_number: self.date_number,
+                court_rank: self.court_rank,
+                section: Some(PrecedentSection {
+                    precedent_id: self.id.clone(),
+                    kind: section.kind,
+                    title: section.title,
+                    position,
+                }),
+                statutes: self.statutes.clone(),
+                statute_keys: self.statute_keys.clone(),
+                cited_precedents: self.cited_precedents.clone(),
+                cited_precedent_ids: self.cited_precedent_ids.clone(),
+            })
+            .collect()
+    }
+}
+
+/// 裁判所名から審級を求める。
+/// 最高裁判所（大審院）は4、高等裁判所は3、地方裁判所と家庭裁判所は2、簡易裁判所は1とし、
+/// 判別できないものは0とする。
+pub fn court_rank(court_name: &str) -> usize {
+    if court_name.contains("最高裁判所") || court_name.contains("大審院") {
+        4
+    } else if court_name.contains("高等裁判所") {
+        3
+    } else if court_name.contains("地方裁判所") || court_name.contains("家庭裁判所") {
+        2
+    } else if court_name.contains("簡易裁判所") {
+        1
+    } else {
+        0
+    }
+}
+
+/// 日付を`yyyymmdd`の形の数にする
+pub fn date_to_number(date: &Date) -> usize {
+    let (year, month, day) = date_to_ad(date);
+    year * 10000 + month * 100 + day
+}
+
+/// 元号での年に足すと西暦での年になる数
+pub fn era_offset(era: &Era) -> usize {
+    match era {
+        Era::Meiji => 1867,
+        Era::Taisho => 1911,
+        Era::Showa => 1925,
+        Era::Heisei => 1988,
+        Era::Reiwa => 2018,
+    }
+}
+
+/// 日付を西暦での年・月・日に変換する。月や日が無い場合は1とする
+pub fn date_to_ad(date: &Date) -> (usize, usize, usize) {
+    (
+        era_offset(&date.era) + date.year,
+        date.month.unwrap_or(1),
+        date.day.unwrap_or(1),
+    )
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -49,6 +413,13 @@ impl LegalDocument {
             LegalDocument::Precedent(p) => p.id.clone(),
         }
     }
+    /// 同じ法令に含まれる文書で共通のID。判例の場合は文書のIDと同じ。
+    pub fn get_group_id(&self) -> String {
+        match self {
+            LegalDocument::Law(l) => format!("{}", l.law_id),
+            LegalDocument::Precedent(p) => p.precedent_id().to_string(),
+        }
+    }
     pub fn get_text(&self) -> String {
         match self {
             LegalDocument::Law(l) => l.text.clone(),
@@ -60,10 +431,144 @@ impl LegalDocument {
 #[derive(Debug, Clone)]
 pub struct LegalDocumentsRegistory {
     meilisearch_client: Client,
+    /// 与えると文書の登録時に埋め込みベクトルを付け、ハイブリッド検索ができるようになる
+    embedder: Option<Arc<dyn Embedder>>,
+    /// 与えると法令名の一覧を取得した結果を使い回す
+    law_names_cache: Option<LawNamesCache>,
+}
+
+/// 法令名の表を使い回す期間
+const LAW_NAMES_CACHE_TTL: Duration = Duration::from_secs(600);
+
+/// 取得した法令名から作った表を保持する。複製したものの間で共有される
+#[derive(Debug, Clone, Default)]
+pub struct LawNamesCache(Arc<RwLock<Option<CachedLawNames>>>);
+
+/// 法令名の表と、その元になった一覧を取得した時刻
+#[derive(Debug)]
+struct CachedLawNames {
+    fetched_at: Instant,
+    table: Arc<LawNameTable>,
+}
+
+impl LawNamesCache {
+    /// 保持している表が古くなっていなければ返す
+    fn get(&self) -> Option<Arc<LawNameTable>> {
+        let cache = self.0.read().ok()?;
+        let cached = cache.as_ref()?;
+        (cached.fetched_at.elapsed() < LAW_NAMES_CACHE_TTL).then(|| cached.table.clone())
+    }
+
+    fn set(&self, table: Arc<LawNameTable>) {
+        if let Ok(mut cache) = self.0.write() {
+            *cache = Some(CachedLawNames {
+                fetched_at: Instant::now(),
+                table,
+            });
+        }
+    }
+}
+
+/// meilisearchに登録する埋め込みベクトルの名前
+const EMBEDDER_NAME: &str = "lawscape";
+
+/// 埋め込みベクトルを付けて登録する文書
+#[derive(Debug, Clone, Serialize)]
+struct EmbeddedLegalDocument<'a> {
+    #[serde(flatten)]
+    document: &'a LegalDocument,
+    #[serde(rename = "_vectors")]
+    vectors: HashMap<&'static str, Vec<f32>>,
 }
 
 const REGISTORY_INDEX_NAME: &str = "legal_documents";
 const REGISTORY_ID_NAME: &str = "id";
+/// 絞り込みに使う属性
+const REGISTORY_FILTERABLE_ATTRIBUTES: [&str; 20] = [
+    "id",
+    "type",
+    "law_id",
+    "name",
+    "index.article",
+    "position",
+    "info.court_name",
+    "info.trial_type",
+    "year",
+    "date_number",
+    "section.kind",
+    "section.precedent_id",
+    "precedent_id",
+    "law_type",
+    "promulgation_year",
+    "status",
+    "statute_keys",
+    "cited_precedent_ids",
+    "reference_keys",
+    "delegated_by",
+    "defined_terms",
+];
+/// 並び替えに使う属性
+const REGISTORY_SORTABLE_ATTRIBUTES: [&str; 2] = ["date_number", "court_rank"];
+/// 検索結果で件数を集計する属性と、集計結果での名前
+const FACET_ATTRIBUTES: [(&str, &str); 8] = [
+    ("type", "type"),
+    ("law_id", "law_id"),
+    ("name", "name"),
+    ("info.court_name", "court"),
+    ("info.trial_type", "trial_type"),
+    ("year", "year"),
+    ("section.kind", "section"),
+    ("law_type", "law_type"),
+];
+/// 一つの属性について集計する値の種類の上限。法令名や法令IDの一覧を取得するために大きめにとる。
+/// meilisearchではこの値を索引ごとにしか設定できないため、検索結果の集計は`SEARCH_FACET_MAX_VALUES`で切り詰める
+const REGISTORY_MAX_VALUES_PER_FACET: usize = 100000;
+/// 検索で取得できる文書の数の上限。判例は判決ごとに一件に数える
+const REGISTORY_MAX_TOTAL_HITS: usize = 1000;
+/// 区分ごとに分けて登録した判例を、検索結果で判決ごとに一件にまとめるための属性。法令の条文は持たないためまとめられない
+const REGISTORY_DISTINCT_ATTRIBUTE: &str = "precedent_id";
+/// 検索結果の集計で返す、一つの属性についての値の種類の上限
+const SEARCH_FACET_MAX_VALUES: usize = 100;
+/// 引用の解決で取得する候補の数
+const CITATION_CANDIDATES_SIZE: usize = 20;
+/// 一度に取得する文書の数
+const REGISTORY_DOCUMENTS_FETCH_SIZE: usize = 1000;
+/// 似た文書を探す際に本文から取り出す語の数
+const SIMILAR_TERMS_SIZE: usize = 10;
+/// 施行されている法令の条文を対象にするフィルタ式。廃止された法令と施行前の版は含めない
+const CURRENT_LAW_FILTER: &str = "type = \"Law\" AND status = \"in_force\"";
+/// 法令IDを指定して取得する際のフィルタ式。廃止された法令は`status`とともに返し、施行前の版は含めない
+const LAW_LOOKUP_FILTER: &str = "type = \"Law\" AND status != \"not_yet_effective\"";
+/// グラフをたどる際に一つの文書から取得する、参照している判例などの数の上限
+const GRAPH_NEIGHBORS_LIMIT: usize = 100;
+/// グラフをたどる際に訪れる文書の数の上限
+const GRAPH_MAX_NODES: usize = 500;
+/// グラフをたどる際に一度の問い合わせでまとめて扱う文書の数
+const GRAPH_BATCH_SIZE: usize = 50;
+/// グラフをたどる際に指定できる辺の数の上限
+pub const GRAPH_MAX_DEPTH: usize = 5;
+/// 委任の階層をたどる深さの上限。法律・政令・府省令とその下の規則を想定する
+const DELEGATION_MAX_DEPTH: usize = 4;
+/// 入力補完の候補を登録するインデックス
+const SUGGESTION_INDEX_NAME: &str = "suggestions";
+/// 入力補完で検索対象とする属性
+const SUGGESTION_SEARCHABLE_ATTRIBUTES: [&str; 2] = ["name", "keywords"];
+/// 入力補完で絞り込みに使う属性
+const SUGGESTION_FILTERABLE_ATTRIBUTES: [&str; 1] = ["type"];
+
+/// フィルタ式の中で文字列として扱えるようにエスケープする
+fn escape_filter_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// フィルタ式の`IN`に渡す値の一覧にする
+fn filter_value_list<'a>(values: impl IntoIterator<Item = &'a str>) -> String {
+    values
+        .into_iter()
+        .map(|v| format!("\"{}\"", escape_filter_value(v)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
 impl LegalDocumentsRegistory {
     /// 検索レジストリへのアクセスを生成
@@ -72,12 +577,102 @@ impl LegalDocumentsRegistory {
             .map_err(|e| LawscapeCoreError::MeilisearchClientError(Box::new(e)))?;
         Ok(Self {
             meilisearch_client: client,
+            embedder: None,
+            law_names_cache: None,
         })
     }
 
+    /// 法令名の一覧を使い回すためのキャッシュを設定する
+    pub fn with_law_names_cache(mut self, law_names_cache: LawNamesCache) -> Self {
+        self.law_names_cache = Some(law_names_cache);
+        self
+    }
+
+    /// 文書や検索語を埋め込みベクトルに変換する方法を設定する
+    pub fn with_embedder(mut self, embedder: Arc<dyn Embedder>) -> Self {
+        self.embedder = Some(embedder);
+        self
+    }
+
+    /// 検索用レジストリの絞り込みなどの設定を行う
+    pub async fn update_settings(&self) -> Result<(), LawscapeCoreError> {
+        let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+        index
+            .set_filterable_attributes(REGISTORY_FILTERABLE_ATTRIBUTES)
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSettingsError(Box::new(e)))?;
+        index
+            .set_sortable_attributes(REGISTORY_SORTABLE_ATTRIBUTES)
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSettingsError(Box::new(e)))?;
+        index
+            .set_faceting(&FacetingSettings {
+                max_values_per_facet: REGISTORY_MAX_VALUES_PER_FACET,
+            })
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSettingsError(Box::new(e)))?;
+        index
+            .set_distinct_attribute(REGISTORY_DISTINCT_ATTRIBUTE)
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSettingsError(Box::new(e)))?;
+        index
+            .set_pagination(PaginationSetting {
+                max_total_hits: REGISTORY_MAX_TOTAL_HITS,
+            })
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSettingsError(Box::new(e)))?;
+        if let Some(embedder) = &self.embedder {
+            let embedder_settings = MeilisearchEmbedder {
+                source: EmbedderSource::UserProvided,
+                dimensions: Some(embedder.dimensions()),
+                ..Default::default()
+            };
+            let settings =
+                Settings::new().with_embedders(HashMap::from([(EMBEDDER_NAME, embedder_settings)]));
+            index
+                .set_settings(&settings)
+                .await
+                .map_err(|e| LawscapeCoreError::MeilisearchSettingsError(Box::new(e)))?;
+        }
+        let suggestion_index = self.meilisearch_client.index(SUGGESTION_INDEX_NAME);
+        suggestion_index
+            .set_searchable_attributes(SUGGESTION_SEARCHABLE_ATTRIBUTES)
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSettingsError(Box::new(e)))?;
+        suggestion_index
+            .set_filterable_attributes(SUGGESTION_FILTERABLE_ATTRIBUTES)
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSettingsError(Box::new(e)))?;
+        Ok(())
+    }
+
     // 検索用レジストリにデータを追加する
     pub async fn add_data(&self, data: &[LegalDocument]) -> Result<(), LawscapeCoreError> {
         let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+        if let Some(embedder) = &self.embedder {
+            let data = data
+                .iter()
+                .map(|document| EmbeddedLegalDocument {
+                    document,
+                    vectors: HashMap::from([(EMBEDDER_NAME, embedder.embed(&document.get_text()))]),
+                })
+                .collect::<Vec<_>>();
+            index
+                .add_documents(&data, Some(REGISTORY_ID_NAME))
+                .await
+                .map_err(|e| LawscapeCoreError::MeilisearchIndexError(Box::new(e)))?;
+        } else {
+            index
+                .add_documents(data, Some(REGISTORY_ID_NAME))
+                .await
+                .map_err(|e| LawscapeCoreError::MeilisearchIndexError(Box::new(e)))?;
+        }
+        Ok(())
+    }
+
+    /// 入力補完の候補を追加する
+    pub async fn add_suggestions(&self, data: &[Suggestion]) -> Result<(), LawscapeCoreError> {
+        let index = self.meilisearch_client.index(SUGGESTION_INDEX_NAME);
         index
             .add_documents(data, Some(REGISTORY_ID_NAME))
             .await
@@ -85,99 +680,1949 @@ impl LegalDocumentsRegistory {
         Ok(())
     }
 
-    /// 検索用レジストリから値を取得する。cancel_scoreは打ち切り値。
-    pub async fn search(
+    /// 入力途中の文字列から法令名や判例の事件名の候補を取得する。
+    /// 末尾の語は前方一致で、表記の揺れはmeilisearchの誤字の許容によって扱う。
+    pub async fn suggest(
         &self,
-        word: &str,
+        prefix: &str,
+        document_type: Option<LegalDocumentType>,
         limit: usize,
-        cancel_score: f64,
-    ) -> Result<Vec<LegalDocumentSearchResult>, LawscapeCoreError> {
+    ) -> Result<Vec<Suggestion>, LawscapeCoreError> {
+        let index = self.meilisearch_client.index(SUGGESTION_INDEX_NAME);
+        let filter = document_type.map(|t| format!("type = \"{}\"", t.suggestion_type_name()));
+        let mut query = index.search();
+        query
+            .with_query(prefix)
+            .with_limit(limit.saturating_mul(SUGGESTION_CANDIDATES_RATE))
+            .with_locales(&["jpn"]);
+        if let Some(filter) = &filter {
+            query.with_filter(filter);
+        }
+        let candidates = query
+            .execute::<Suggestion>()
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSearchError(Box::new(e)))?
+            .hits
+            .into_iter()
+            .map(|hit| hit.result)
+            .collect();
+        Ok(select_suggestions(candidates, prefix, limit))
+    }
+
+    /// IDを指定して文書を取得する。存在しない場合は`None`を返す。
+    pub async fn get_document(&self, id: &str) -> Result<Option<LegalDocument>, LawscapeCoreError> {
+        let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+        match index.get_document::<LegalDocument>(id).await {
+            Ok(document) => Ok(Some(document)),
+            Err(MeilisearchError::Meilisearch(e))
+                if matches!(e.error_code, ErrorCode::DocumentNotFound) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(LawscapeCoreError::MeilisearchDocumentError(Box::new(e))),
+        }
+    }
+
+    /// IDを指定して判例を取得する。存在しない場合や判例でない場合は`None`を返す。
+    /// 区分ごとに分けて登録した判例は、区分をつなげて一つの判例にして返す。
+    pub async fn get_precedent(&self, id: &str) -> Result<Option<Precedent>, LawscapeCoreError> {
+        let document = self.get_document(id).await?;
+        match document {
+            Some(LegalDocument::Precedent(p)) => Ok(Some(p)),
+            Some(LegalDocument::Law(_)) => Ok(None),
+            None => self.join_precedent_sections(id).await,
+        }
+    }
+
+    /// 区分ごとに分けて登録した判例を、区分をつなげて一つの判例にする。区分が無い場合は`None`を返す
+    async fn join_precedent_sections(
+        &self,
+        precedent_id: &str,
+    ) -> Result<Option<Precedent>, LawscapeCoreError> {
+        let sections = self.get_precedent_sections(precedent_id).await?;
+        let Some(first) = sections.first() else {
+            return Ok(None);
+        };
+        let text = sections
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(Some(Precedent {
+            id: precedent_id.to_string(),
+            precedent_id: precedent_id.to_string(),
+            text,
+            section: None,
+            ..first.clone()
+        }))
+    }
+
+    /// 判決全体のIDを指定して、区分ごとに分けて登録した判例を出現順に全て取得する
+    pub async fn get_precedent_sections(
+        &self,
+        precedent_id: &str,
+    ) -> Result<Vec<Precedent>, LawscapeCoreError> {
+        let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+        let filter = format!(
+            "type = \"Precedent\" AND section.precedent_id = \"{}\"",
+            escape_filter_value(precedent_id)
+        );
+        let mut section_list = DocumentsQuery::new(&index)
+            .with_filter(&filter)
+            .with_limit(REGISTORY_DOCUMENTS_FETCH_SIZE)
+            .execute::<LegalDocument>()
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchDocumentError(Box::new(e)))?
+            .results
+            .into_iter()
+            .filter_map(|d| match d {
+                LegalDocument::Precedent(p) => Some(p),
+                LegalDocument::Law(_) => None,
+            })
+            .collect::<Vec<_>>();
+        section_list.sort_by_key(|p| p.section.as_ref().map(|s| s.position));
+        Ok(section_list)
+    }
+
+    /// 法令IDを指定してその法令の条文を全て取得する。条文は法令内での出現順に並ぶ。
+    /// 廃止された法令も返すため、施行状況は`status`で確かめる。
+    pub async fn get_law_articles(&self, law_id: &str) -> Result<Vec<Law>, LawscapeCoreError> {
+        let filter = format!(
+            "{LAW_LOOKUP_FILTER} AND law_id = \"{}\"",
+            escape_filter_value(law_id)
+        );
+        let mut law_list = self.fetch_laws(&filter).await?;
+        law_list.sort_by_key(|l| {
+            let paragraph = l.paragraph.as_ref().and_then(|p| p.parse::<usize>().ok());
+            (l.position, paragraph)
+        });
+        Ok(law_list)
+    }
+
+    /// フィルタ式に当てはまる法令の条文を全て取得する。順番は定まらない
+    async fn fetch_laws(&self, filter: &str) -> Result<Vec<Law>, LawscapeCoreError> {
+        let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+        let mut law_list = Vec::new();
+        let mut offset = 0;
+        loop {
+            let documents = DocumentsQuery::new(&index)
+                .with_filter(filter)
+                .with_offset(offset)
+                .with_limit(REGISTORY_DOCUMENTS_FETCH_SIZE)
+                .execute::<LegalDocument>()
+                .await
+                .map_err(|e| LawscapeCoreError::MeilisearchDocumentError(Box::new(e)))?
+                .results;
+            let size = documents.len();
+            law_list.extend(documents.into_iter().filter_map(|d| match d {
+                LegalDocument::Law(l) => Some(l),
+                LegalDocument::Precedent(_) => None,
+            }));
+            if size < REGISTORY_DOCUMENTS_FETCH_SIZE {
+                break;
+            }
+            offset += size;
+        }
+        Ok(law_list)
+    }
+
+    /// 「民法709条」のような引用から該当する条文を探す
+    pub async fn resolve_citation(
+        &self,
+        cite: &str,
+    ) -> Result<CitationResolution, LawscapeCoreError> {
+        let citation = citation::parse_citation(cite)?;
         let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
-        let mut result = index
+        let filter = match &citation.article {
+            Some(article) => format!(
+                "{CURRENT_LAW_FILTER} AND index.article = \"{}\"",
+                escape_filter_value(article)
+            ),
+            None => CURRENT_LAW_FILTER.to_string(),
+        };
+        let hits = index
             .search()
-            .with_query(word)
-            .with_limit(limit)
+            .with_query(&citation.official_law_name)
+            .with_filter(&filter)
+            .with_limit(CITATION_CANDIDATES_SIZE)
             .with_locales(&["jpn"])
-            .with_ranking_score_threshold(cancel_score)
             .execute::<LegalDocument>()
             .await
             .map_err(|e| LawscapeCoreError::MeilisearchSearchError(Box::new(e)))?
             .hits;
-        result.sort_by(|t1, t2| t2.ranking_score.partial_cmp(&t1.ranking_score).unwrap());
-        let document_list = result
-            .iter()
-            .take(limit)
-            .map(|search_result| LegalDocumentSearchResult {
-                document: search_result.clone().result,
-                score: search_result.ranking_score,
+        let (mut matches, candidates): (Vec<Law>, Vec<Law>) = hits
+            .into_iter()
+            .filter_map(|hit| match hit.result {
+                LegalDocument::Law(l) => Some(l),
+                LegalDocument::Precedent(_) => None,
+            })
+            .partition(|l| l.name == citation.official_law_name || l.name == citation.law_name);
+        // 条番号の指定が無い場合は法令ごとに先頭の条文のみを返す
+        if citation.article.is_none() {
+            matches.sort_by_key(|l| (l.law_id.to_string(), l.position));
+            matches.dedup_by(|l1, l2| l1.law_id == l2.law_id);
+        }
+        // 項ごとに分けて登録されている場合は指定された項の文書だけを残す
+        if let Some(paragraph) = citation.paragraph {
+            let paragraph = paragraph.to_string();
+            matches.retain(|l| l.paragraph.as_ref().is_none_or(|p| p == &paragraph));
+        }
+        let provisions = match citation.paragraph {
+            Some(paragraph) => matches
+                .iter()
+                .filter_map(|l| l.locate(paragraph, citation.item))
+                .collect(),
+            None => Vec::new(),
+        };
+        Ok(CitationResolution {
+            citation,
+            matches,
+            candidates,
+            provisions,
+        })
+    }
+
+    /// 「民法709条」のような引用から、判例を参照している条文で絞り込むための値を求める。
+    /// 引用の法令が登録されていない場合は`None`を返す。
+    pub async fn resolve_statute_key(
+        &self,
+        cite: &str,
+    ) -> Result<Option<String>, LawscapeCoreError> {
+        let citation = citation::parse_citation(cite)?;
+        let law_list = self
+            .find_law_articles_by_name(&citation.official_law_name, None)
+            .await?;
+        Ok(law_list
+            .first()
+            .map(|l| citation::statute_key(&l.law_id.to_string(), citation.article.as_deref())))
+    }
+
+    /// 「民法709条」のような引用で指定した条文を参照している判例を、判決日の新しい順に取得する。
+    /// 引用の法令が登録されていない場合は`None`を返す。
+    pub async fn find_precedents_by_statute(
+        &self,
+        cite: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Option<Vec<StatutePrecedent>>, LawscapeCoreError> {
+        let Some(key) = self.resolve_statute_key(cite).await? else {
+            return Ok(None);
+        };
+        let filter = format!(
+            "type = \"Precedent\" AND statute_keys = \"{}\"",
+            escape_filter_value(&key)
+        );
+        let precedents = self
+            .find_precedents(&filter, limit, offset)
+            .await?
+            .into_iter()
+            .map(|p| StatutePrecedent {
+                precedent_id: p.precedent_id().to_string(),
+                statutes: p
+                    .statutes
+                    .iter()
+                    .filter(|s| s.keys().contains(&key))
+                    .cloned()
+                    .collect(),
+                info: p.info,
             })
             .collect();
-        Ok(document_list)
+        Ok(Some(precedents))
     }
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LegalDocumentSearchResult {
-    pub score: Option<f64>,
-    pub document: LegalDocument,
-}
+    /// IDを指定して、判例が引用している判例と、判例を引用している判例を取得する。
+    /// 引用している判例は判決日の新しい順に並ぶ。判例が存在しない場合は`None`を返す。
+    pub async fn get_precedent_citations(
+        &self,
+        id: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Option<PrecedentCitations>, LawscapeCoreError> {
+        let Some(precedent) = self.get_precedent(id).await? else {
+            return Ok(None);
+        };
+        let precedent_id = precedent.precedent_id().to_string();
+        let filter = format!(
+            "type = \"Precedent\" AND cited_precedent_ids = \"{}\"",
+            escape_filter_value(&precedent_id)
+        );
+        let cited_by = self
+            .find_precedents(&filter, limit, offset)
+            .await?
+            .into_iter()
+            .map(|p| CitingPrecedent {
+                precedent_id: p.precedent_id().to_string(),
+                info: p.info,
+            })
+            .collect();
+        Ok(Some(PrecedentCitations {
+            precedent_id,
+            cites: precedent.cited_precedents,
+            cited_by,
+        }))
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LegalDocumentDependencies {
-    /// 同じ法令に含まれる法令文書など。判例の場合は通常一つ。
-    pub contents: Vec<LegalDocumentSearchResult>,
-    /// 参照している法令文書
-    pub parents: Vec<String>,
-    /// 参照されている法令文書
-    pub children: Vec<String>,
-}
+    /// 文書の周りのグラフを取得する。法令は法令ID、条は`{法令ID}:{条番号}`、判例は判決全体のIDで指定する。
+    /// 文書が存在しない場合は`None`を返す。
+    pub async fn get_graph_neighborhood(
+        &self,
+        id: &str,
+        traversal: &GraphTraversal,
+    ) -> Result<Option<LegalGraph>, LawscapeCoreError> {
+        let Some(start) = self.get_graph_node(id).await? else {
+            return Ok(None);
+        };
+        let graph = graph::explore_neighborhood(self, start, traversal, GRAPH_MAX_NODES).await?;
+        Ok(Some(graph))
+    }
 
-pub fn analyze_search_result_dependencies(
-    legal_documents: &[LegalDocumentSearchResult],
-) -> HashMap<String, LegalDocumentDependencies> {
-    let mut id_list = legal_documents
-        .iter()
-        .map(|d| d.document.get_id())
-        .collect::<Vec<String>>();
-    id_list.sort();
-    id_list.dedup();
-    let mut contents_list = Vec::new();
-    for id in id_list.iter() {
-        let documents = legal_documents
+    /// 二つの文書を結ぶ最短の経路を探す。`traversal.depth`は経路に含まれる辺の数の上限となる。
+    /// 文書が存在しない場合や経路が見つからない場合は`None`を返す。
+    pub async fn find_graph_path(
+        &self,
+        from: &str,
+        to: &str,
+        traversal: &GraphTraversal,
+    ) -> Result<Option<GraphPath>, LawscapeCoreError> {
+        let Some(start) = self.get_graph_node(from).await? else {
+            return Ok(None);
+        };
+        graph::explore_path(self, start, to, traversal, GRAPH_MAX_NODES).await
+    }
+
+    /// IDからグラフのノードを求める。`:`を含む場合は条、それ以外は法令、判例の順に探す。
+    /// 文書が存在しない場合は`None`を返す。
+    async fn get_graph_node(&self, id: &str) -> Result<Option<GraphNode>, LawscapeCoreError> {
+        let kinds = if id.contains(':') {
+            vec![GraphNodeKind::Article]
+        } else {
+            vec![GraphNodeKind::Law, GraphNodeKind::Precedent]
+        };
+        let ids = kinds
             .iter()
-            .filter(|d| &d.document.get_id() == id)
-            .cloned()
+            .map(|kind| (id.to_string(), *kind))
             .collect::<Vec<_>>();
-        let document = documents.first().map(|d| d.document.clone());
-        let name = match document {
-            Some(LegalDocument::Law(l)) => Some(l.name.clone()),
-            _ => None,
+        let nodes = self.nodes(&ids).await?;
+        Ok(kinds
+            .iter()
+            .find_map(|kind| nodes.iter().find(|node| node.kind == *kind))
+            .cloned())
+    }
+
+    /// 法令IDと条番号が一致し、`base_filter`に当てはまる条文を取得する
+    async fn find_law_documents(
+        &self,
+        base_filter: &str,
+        law_id: &str,
+        article: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Law>, LawscapeCoreError> {
+        let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+        let mut filter = format!(
+            "{base_filter} AND law_id = \"{}\"",
+            escape_filter_value(law_id)
+        );
+        if let Some(article) = article {
+            filter.push_str(&format!(
+                " AND index.article = \"{}\"",
+                escape_filter_value(article)
+            ));
+        }
+        let law_list = index
+            .search()
+            .with_filter(&filter)
+            .with_limit(limit)
+            .execute::<LegalDocument>()
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSearchError(Box::new(e)))?
+            .hits
+            .into_iter()
+            .filter_map(|hit| match hit.result {
+                LegalDocument::Law(l) => Some(l),
+                LegalDocument::Precedent(_) => None,
+            })
+            .collect();
+        Ok(law_list)
+    }
+
+    /// 条のIDの一覧から、条文を取得するためのフィルタ式を求める
+    fn article_filter(article_ids: &[&str]) -> String {
+        let conditions = article_ids
+            .iter()
+            .filter_map(|id| id.split_once(':'))
+            .map(|(law_id, article)| {
+                format!(
+                    "(law_id = \"{}\" AND index.article = \"{}\")",
+                    escape_filter_value(law_id),
+                    escape_filter_value(article)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        format!("{CURRENT_LAW_FILTER} AND ({conditions})")
+    }
+
+    /// 法令や条から出る辺をまとめて取得する
+    async fn outgoing_law_edges(
+        &self,
+        ids: &[&str],
+        kind: GraphNodeKind,
+        edges: &mut Vec<NeighborEdge>,
+    ) -> Result<(), LawscapeCoreError> {
+        let filter = match kind {
+            GraphNodeKind::Article => Self::article_filter(ids),
+            _ => format!(
+                "{CURRENT_LAW_FILTER} AND law_id IN [{}]",
+                filter_value_list(ids.iter().copied())
+            ),
         };
-        contents_list.push((id, name, documents.clone()));
+        let mut law_map: HashMap<String, Vec<Law>> = HashMap::new();
+        for law in self.fetch_laws(&filter).await? {
+            let id = match kind {
+                GraphNodeKind::Article => {
+                    citation::statute_key(&law.law_id.to_string(), Some(&law.index.article))
+                }
+                _ => law.law_id.to_string(),
+            };
+            law_map.entry(id).or_default().push(law);
+        }
+        for (id, law_list) in law_map {
+            let delegated_by = match kind {
+                GraphNodeKind::Law => law_list.iter().find_map(|l| l.delegated_by.clone()),
+                _ => None,
+            };
+            let mut keys = law_list
+                .into_iter()
+                .flat_map(|l| l.reference_keys)
+                // 法令からは法令単位の参照のみをたどる。委任元への参照は委任の辺として扱う
+                .filter(|key| {
+                    kind == GraphNodeKind::Article
+                        || (!key.contains(':') && delegated_by.as_ref() != Some(key))
+                })
+                .collect::<Vec<String>>();
+            keys.sort();
+            keys.dedup();
+            for key in keys.iter() {
+                edges.push(NeighborEdge::outgoing(
+                    &id,
+                    key,
+                    GraphEdgeKind::Reference,
+                    GraphNodeKind::from_statute_key(key),
+                ));
+            }
+            if let Some(parent_id) = &delegated_by {
+                edges.push(NeighborEdge::outgoing(
+                    &id,
+                    parent_id,
+                    GraphEdgeKind::Delegation,
+                    GraphNodeKind::Law,
+                ));
+            }
+        }
+        Ok(())
     }
-    let mut paretns_list: Vec<Vec<String>> = vec![Vec::new(); id_list.len()];
-    let mut children_list: Vec<Vec<String>> = vec![Vec::new(); id_list.len()];
-    for (i, (id, name, _)) in contents_list.iter().enumerate() {
-        if let Some(name) = name {
-            for (j, (id2, name2, documents2)) in contents_list.iter().enumerate() {
-                if id == id2 {
+
+    /// 判決全体のIDの一覧に当てはまる判例を取得する
+    async fn find_precedents_by_ids(
+        &self,
+        precedent_ids: &[&str],
+    ) -> Result<Vec<Precedent>, LawscapeCoreError> {
+        let list = filter_value_list(precedent_ids.iter().copied());
+        let filter = format!("type = \"Precedent\" AND precedent_id IN [{list}]");
+        self.find_precedents(&filter, precedent_ids.len(), 0).await
+    }
+
+    /// 法令や条に入る辺をまとめて取得する
+    async fn incoming_law_edges(
+        &self,
+        ids: &[&str],
+        kind: GraphNodeKind,
+        traversal: &GraphTraversal,
+        edges: &mut Vec<NeighborEdge>,
+    ) -> Result<(), LawscapeCoreError> {
+        let list = filter_value_list(ids.iter().copied());
+        if traversal.allows(GraphEdgeKind::Reference) {
+            let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+            let filter = format!("{CURRENT_LAW_FILTER} AND reference_keys IN [{list}]");
+            let hits = index
+                .search()
+                .with_filter(&filter)
+                .with_limit(REGISTORY_DOCUMENTS_FETCH_SIZE)
+                .execute::<LegalDocument>()
+                .await
+                .map_err(|e| LawscapeCoreError::MeilisearchSearchError(Box::new(e)))?
+                .hits;
+            let mut sources: HashMap<&str, Vec<(String, GraphNodeKind)>> = HashMap::new();
+            for hit in hits {
+                let LegalDocument::Law(l) = hit.result else {
                     continue;
                 };
-                // 法令名称が含まれるかどうかの判定
-                let mut is_contains = false;
-                if let Some(name2) = name2 {
-                    is_contains = name2.contains(name);
+                for id in ids
+                    .iter()
+                    .filter(|id| l.reference_keys.iter().any(|k| k == *id))
+                {
+                    // 条からは条単位で参照元をたどる
+                    let source = match kind {
+                        GraphNodeKind::Article => (
+                            citation::statute_key(&l.law_id.to_string(), Some(&l.index.article)),
+                            GraphNodeKind::Article,
+                        ),
+                        _ => (l.law_id.to_string(), GraphNodeKind::Law),
+                    };
+                    sources.entry(*id).or_default().push(source);
                 }
-                for document2 in documents2.iter() {
-                    if is_contains {
-                        break;
-                    }
-                    is_contains = document2.document.get_text().contains(name);
+            }
+            for (id, mut source_list) in sources {
+                source_list.sort();
+                source_list.dedup();
+                source_list.truncate(GRAPH_NEIGHBORS_LIMIT);
+                for (source, source_kind) in source_list.iter() {
+                    edges.push(NeighborEdge::incoming(
+                        id,
+                        source,
+                        GraphEdgeKind::Reference,
+                        *source_kind,
+                    ));
+                }
+            }
+        }
+        if kind == GraphNodeKind::Law && traversal.allows(GraphEdgeKind::Delegation) {
+            let filter =
+                format!("{CURRENT_LAW_FILTER} AND position = 0 AND delegated_by IN [{list}]");
+            for law in self.fetch_laws(&filter).await? {
+                if let Some(parent_id) = &law.delegated_by {
+                    edges.push(NeighborEdge::incoming(
+                        parent_id,
+                        &law.law_id.to_string(),
+                        GraphEdgeKind::Delegation,
+                        GraphNodeKind::Law,
+                    ));
+                }
+            }
+        }
+        if traversal.allows(GraphEdgeKind::Statute) {
+            let filter = format!("type = \"Precedent\" AND statute_keys IN [{list}]");
+            self.push_citing_precedents(ids, &filter, GraphEdgeKind::Statute, edges)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// `filter`に当てはまる判例から`ids`の文書に入る辺を、一つの文書あたり`GRAPH_NEIGHBORS_LIMIT`個まで加える。
+    /// まとめて取得するため、判決日の新しいものから順に全体で`ids`の数の`GRAPH_NEIGHBORS_LIMIT`倍までを対象とする。
+    /// ただし`REGISTORY_MAX_TOTAL_HITS`を超える分は取得できない。
+    async fn push_citing_precedents(
+        &self,
+        ids: &[&str],
+        filter: &str,
+        kind: GraphEdgeKind,
+        edges: &mut Vec<NeighborEdge>,
+    ) -> Result<(), LawscapeCoreError> {
+        let precedents = self
+            .find_precedents(filter, GRAPH_NEIGHBORS_LIMIT * ids.len(), 0)
+            .await?;
+        for id in ids.iter() {
+            let citing = precedents
+                .iter()
+                .filter(|p| match kind {
+                    GraphEdgeKind::Citation => p.cited_precedent_ids.iter().any(|c| c == id),
+                    _ => p.statute_keys.iter().any(|k| k == id),
+                })
+                .take(GRAPH_NEIGHBORS_LIMIT);
+            for precedent in citing {
+                edges.push(NeighborEdge::incoming(
+                    id,
+                    precedent.precedent_id(),
+                    kind,
+                    GraphNodeKind::Precedent,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 法令IDを指定して、法令の中で定義されている用語を条文に現れた順に取得する。
+    /// 法令が存在しない場合は`None`を返す。
+    pub async fn get_law_definitions(
+        &self,
+        law_id: &str,
+    ) -> Result<Option<Vec<DefinedTerm>>, LawscapeCoreError> {
+        let law_articles = self.get_law_articles(law_id).await?;
+        if law_articles.is_empty() {
+            return Ok(None);
+        }
+        let definitions = law_articles
+            .iter()
+            .flat_map(DefinedTerm::from_law)
+            .collect();
+        Ok(Some(definitions))
+    }
+
+    /// 用語を定義している法令の条文を探し、定義を法令IDと条文の出現順に取得する
+    pub async fn find_definitions(
+        &self,
+        term: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<DefinedTerm>, LawscapeCoreError> {
+        let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+        let filter = format!(
+            "{CURRENT_LAW_FILTER} AND defined_terms = \"{}\"",
+            escape_filter_value(term)
+        );
+        let mut law_list = Vec::new();
+        let mut search_offset = 0;
+        loop {
+            let documents = DocumentsQuery::new(&index)
+                .with_filter(&filter)
+                .with_offset(search_offset)
+                .with_limit(REGISTORY_DOCUMENTS_FETCH_SIZE)
+                .execute::<LegalDocument>()
+                .await
+                .map_err(|e| LawscapeCoreError::MeilisearchDocumentError(Box::new(e)))?
+                .results;
+            let size = documents.len();
+            law_list.extend(documents.into_iter().filter_map(|d| match d {
+                LegalDocument::Law(l) => Some(l),
+                LegalDocument::Precedent(_) => None,
+            }));
+            if size < REGISTORY_DOCUMENTS_FETCH_SIZE {
+                break;
+            }
+            search_offset += size;
+        }
+        law_list.sort_by(|l1, l2| {
+            l1.law_id
+                .to_string()
+                .cmp(&l2.law_id.to_string())
+                .then_with(|| l1.position.cmp(&l2.position))
+        });
+        let definitions = law_list
+            .iter()
+            .flat_map(DefinedTerm::from_law)
+            .filter(|d| d.definition.term == term)
+            .skip(offset)
+            .take(limit)
+            .collect();
+        Ok(definitions)
+    }
+
+    /// 法令から委任の関係をたどり、委任先の法令を木の形で取得する。
+    /// 法令が存在しない場合は`None`を返す。
+    pub async fn get_delegation_tree(
+        &self,
+        law_id: &str,
+    ) -> Result<Option<DelegationNode>, LawscapeCoreError> {
+        let Some(root_law) = self
+            .find_law_documents(LAW_LOOKUP_FILTER, law_id, None, 1)
+            .await?
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
+        let mut root = DelegationNode::from_law(&root_law, Vec::new());
+        let mut visited = HashSet::from([root.law_id.clone()]);
+        // 委任元の法令IDと委任先の法令の組を階層ごとに集める
+        let mut levels: Vec<Vec<(String, DelegationNode)>> = Vec::new();
+        let mut frontier = vec![root.law_id.clone()];
+        for _ in 0..DELEGATION_MAX_DEPTH {
+            let mut level = Vec::new();
+            for parent_id in frontier.iter() {
+                let child_ids = self.find_delegated_law_ids(parent_id).await?;
+                if child_ids.is_empty() {
+                    continue;
+                }
+                let parent_articles = self.get_law_articles(parent_id).await?;
+                for child_id in child_ids {
+                    if !visited.insert(child_id.clone()) {
+                        continue;
+                    }
+                    let Some(child_law) = self
+                        .find_law_documents(CURRENT_LAW_FILTER, &child_id, None, 1)
+                        .await?
+                        .into_iter()
+                        .next()
+                    else {
+                        continue;
+                    };
+                    let articles =
+                        delegation::delegating_articles(&parent_articles, child_law.law_type);
+                    level.push((
+                        parent_id.clone(),
+                        DelegationNode::from_law(&child_law, articles),
+                    ));
+                }
+            }
+            if level.is_empty() {
+                break;
+            }
+            frontier = level.iter().map(|(_, node)| node.law_id.clone()).collect();
+            levels.push(level);
+        }
+        // 深い階層から順に委任元のノードへまとめる
+        let mut children_map: HashMap<String, Vec<DelegationNode>> = HashMap::new();
+        for level in levels.into_iter().rev() {
+            for (parent_id, mut node) in level {
+                node.children = children_map.remove(&node.law_id).unwrap_or_default();
+                children_map.entry(parent_id).or_default().push(node);
+            }
+        }
+        root.children = children_map.remove(&root.law_id).unwrap_or_default();
+        Ok(Some(root))
+    }
+
+    /// 委任元が指定した法令である法令の法令IDを取得する
+    async fn find_delegated_law_ids(&self, law_id: &str) -> Result<Vec<String>, LawscapeCoreError> {
+        let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+        let filter = format!(
+            "{CURRENT_LAW_FILTER} AND delegated_by = \"{}\"",
+            escape_filter_value(law_id)
+        );
+        let facet_distribution = index
+            .search()
+            .with_filter(&filter)
+            .with_facets(Selectors::Some(&["law_id"]))
+            .with_limit(0)
+            .execute::<LegalDocument>()
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSearchError(Box::new(e)))?
+            .facet_distribution;
+        let mut law_ids = facet_distribution
+            .and_then(|mut distribution| distribution.remove("law_id"))
+            .map(|ids| ids.into_keys().collect::<Vec<String>>())
+            .unwrap_or_default();
+        law_ids.sort();
+        Ok(law_ids)
+    }
+
+    /// フィルタ式に当てはまる判例を判決日の新しい順に取得する。
+    /// 区分ごとに分けて登録した判例は`REGISTORY_DISTINCT_ATTRIBUTE`によって判決ごとに一つの区分にまとまる。
+    /// 取得できるのは先頭から`REGISTORY_MAX_TOTAL_HITS`件までとなる。
+    async fn find_precedents(
+        &self,
+        filter: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<Precedent>, LawscapeCoreError> {
+        let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+        let precedents = index
+            .search()
+            .with_filter(filter)
+            .with_sort(&["date_number:desc"])
+            .with_offset(offset)
+            .with_limit(limit)
+            .execute::<LegalDocument>()
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSearchError(Box::new(e)))?
+            .hits
+            .into_iter()
+            .filter_map(|hit| match hit.result {
+                LegalDocument::Precedent(p) => Some(p),
+                LegalDocument::Law(_) => None,
+            })
+            .collect();
+        Ok(precedents)
+    }
+
+    /// 登録されている法令名から、文章に現れる法令名を絞り込むための表を作る。
+    /// キャッシュが設定されていればそれを使う
+    async fn get_law_name_table(&self) -> Result<Arc<LawNameTable>, LawscapeCoreError> {
+        if let Some(table) = self.law_names_cache.as_ref().and_then(|c| c.get()) {
+            return Ok(table);
+        }
+        let table = Arc::new(LawNameTable::from_names(self.fetch_law_names().await?));
+        if let Some(cache) = &self.law_names_cache {
+            cache.set(table.clone());
+        }
+        Ok(table)
+    }
+
+    async fn fetch_law_names(&self) -> Result<Vec<String>, LawscapeCoreError> {
+        let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+        let facet_distribution = index
+            .search()
+            .with_filter(CURRENT_LAW_FILTER)
+            .with_facets(Selectors::Some(&["name"]))
+            .with_limit(0)
+            .execute::<LegalDocument>()
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSearchError(Box::new(e)))?
+            .facet_distribution;
+        let mut law_names = facet_distribution
+            .and_then(|mut distribution| distribution.remove("name"))
+            .map(|names| names.into_keys().collect::<Vec<String>>())
+            .unwrap_or_default();
+        law_names.sort();
+        Ok(law_names)
+    }
+
+    /// 法令名と条番号が一致する条文を取得する。条番号が無い場合は法令の先頭の条文を返す。
+    async fn find_law_articles_by_name(
+        &self,
+        law_name: &str,
+        article: Option<&str>,
+    ) -> Result<Vec<Law>, LawscapeCoreError> {
+        let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+        let mut filter = format!(
+            "{CURRENT_LAW_FILTER} AND name = \"{}\"",
+            escape_filter_value(law_name)
+        );
+        if let Some(article) = article {
+            filter.push_str(&format!(
+                " AND index.article = \"{}\"",
+                escape_filter_value(article)
+            ));
+        }
+        let mut law_list = index
+            .search()
+            .with_filter(&filter)
+            .with_limit(CITATION_CANDIDATES_SIZE)
+            .execute::<LegalDocument>()
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSearchError(Box::new(e)))?
+            .hits
+            .into_iter()
+            .filter_map(|hit| match hit.result {
+                LegalDocument::Law(l) => Some(l),
+                LegalDocument::Precedent(_) => None,
+            })
+            .collect::<Vec<Law>>();
+        law_list.sort_by_key(|l| l.position);
+        if article.is_none() {
+            law_list.truncate(1);
+        }
+        Ok(law_list)
+    }
+
+    /// 判決日が`date_number`の判例から、引用を対応付けるための表を作る
+    async fn get_precedent_table(
+        &self,
+        date_number: usize,
+    ) -> Result<PrecedentTable, LawscapeCoreError> {
+        let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+        let hits = index
+            .search()
+            .with_filter(&format!(
+                "type = \"Precedent\" AND date_number = {date_number}"
+            ))
+            .with_limit(REGISTORY_DOCUMENTS_FETCH_SIZE)
+            .execute::<LegalDocument>()
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSearchError(Box::new(e)))?
+            .hits;
+        // 区分ごとに登録された判例は判決全体のIDにまとめる
+        let mut precedent_ids = HashSet::new();
+        let precedents = hits
+            .into_iter()
+            .filter_map(|hit| match hit.result {
+                LegalDocument::Precedent(p) => Some(p),
+                LegalDocument::Law(_) => None,
+            })
+            .filter(|p| precedent_ids.insert(p.precedent_id().to_string()))
+            .map(|p| {
+                (
+                    p.precedent_id().to_string(),
+                    p.info.court_name.clone(),
+                    date_to_ad(&p.info.date),
+                )
+            })
+            .collect::<Vec<_>>();
+        Ok(PrecedentTable::new(precedents))
+    }
+
+    /// 文章中の法令や判例への参照を探し、登録されている文書と対応付ける
+    pub async fn annotate(&self, text: &str) -> Result<Vec<Annotation>, LawscapeCoreError> {
+        let law_names = self.get_law_name_table().await?;
+        let mut annotations = Vec::new();
+        // 同じ参照を何度も問い合わせないようにする
+        let mut resolved: HashMap<(String, Option<String>), Vec<Law>> = HashMap::new();
+        // 文章に現れる二文字から始まる法令名のみを探す
+        for reference in citation::find_statute_references(text, &law_names.candidates(text)) {
+            let Some(official_law_name) = reference.official_law_name.clone() else {
+                annotations.push(Annotation {
+                    reference: AnnotationReference::Statute(reference),
+                    law_id: None,
+                    document_ids: Vec::new(),
+                    confidence: UNRESOLVED_CONFIDENCE,
+                });
+                continue;
+            };
+            let key = (official_law_name, reference.article.clone());
+            let law_list = match resolved.get(&key) {
+                Some(law_list) => law_list.clone(),
+                None => {
+                    let law_list = self
+                        .find_law_articles_by_name(&key.0, key.1.as_deref())
+                        .await?;
+                    resolved.insert(key, law_list.clone());
+                    law_list
+                }
+            };
+            let mut confidence = if law_list.is_empty() {
+                UNRESOLVED_CONFIDENCE
+            } else {
+                1.0
+            };
+            if reference.law_name != reference.official_law_name {
+                confidence *= ABBREVIATION_CONFIDENCE_RATE;
+            }
+            if reference.is_inferred {
+                confidence *= INFERRED_CONFIDENCE_RATE;
+            }
+            annotations.push(Annotation {
+                law_id: law_list.first().map(|l| l.law_id.to_string()),
+                document_ids: if reference.article.is_some() {
+                    law_list.iter().map(|l| l.id.clone()).collect()
+                } else {
+                    Vec::new()
+                },
+                reference: AnnotationReference::Statute(reference),
+                confidence,
+            });
+        }
+        // 同じ判決日の判例を何度も問い合わせないようにする
+        let mut precedent_tables: HashMap<usize, PrecedentTable> = HashMap::new();
+        for reference in citation::find_precedent_references(text) {
+            let citation = &reference.citation;
+            let date_number = citation.year * 10000 + citation.month * 100 + citation.day;
+            if !precedent_tables.contains_key(&date_number) {
+                let table = self.get_precedent_table(date_number).await?;
+                precedent_tables.insert(date_number, table);
+            }
+            let precedent_id = precedent_tables[&date_number].resolve(citation).cloned();
+            annotations.push(Annotation {
+                law_id: None,
+                confidence: if precedent_id.is_some() {
+                    1.0
+                } else {
+                    UNRESOLVED_CONFIDENCE
+                },
+                document_ids: precedent_id.into_iter().collect(),
+                reference: AnnotationReference::Precedent(reference),
+            });
+        }
+        annotations.sort_by_key(|a| a.reference.span().start);
+        Ok(annotations)
+    }
+
+    /// IDを指定した文書と似た文書を探す。元の文書は結果に含まない。
+    /// 判決全体のIDを指定した場合は区分をつなげた判決文を元にする。
+    /// 判例の場合は同じ判決の他の区分も結果に含まない。
+    /// 元の文書が存在しない場合は`None`を返す。
+    pub async fn find_similar(
+        &self,
+        id: &str,
+        options: &SearchOptions,
+    ) -> Result<Option<LegalDocumentSearchResults>, LawscapeCoreError> {
+        let document = match self.get_document(id).await? {
+            Some(document) => document,
+            None => match self.join_precedent_sections(id).await? {
+                Some(precedent) => LegalDocument::Precedent(precedent),
+                None => return Ok(None),
+            },
+        };
+        let filter = match &document {
+            LegalDocument::Law(_) => format!("NOT id = \"{}\"", escape_filter_value(id)),
+            LegalDocument::Precedent(p) => {
+                let precedent_id = escape_filter_value(p.precedent_id());
+                format!(
+                    "NOT id = \"{precedent_id}\" AND NOT section.precedent_id = \"{precedent_id}\""
+                )
+            }
+        };
+        let terms = similar::extract_salient_terms(&document.get_text(), SIMILAR_TERMS_SIZE);
+        if terms.is_empty() {
+            return Ok(Some(LegalDocumentSearchResults {
+                hits: Vec::new(),
+                offset: options.offset,
+                limit: options.limit,
+                estimated_total_hits: Some(0),
+                facets: None,
+            }));
+        }
+        // 重要な語から順に並べ、一致する文書が少ない場合は末尾の語から外して検索させる
+        let compiled_query = CompiledQuery {
+            query: terms.join(" "),
+            filter: Some(filter),
+            is_text_only: true,
+            semantic_query: terms.join(" "),
+        };
+        let search_result = self
+            .execute_search(
+                &compiled_query,
+                false,
+                options.limit,
+                options.offset,
+                options,
+            )
+            .await?;
+        let hits = search_result
+            .hits
+            .iter()
+            .map(|search_result| LegalDocumentSearchResult {
+                document: search_result.result.clone(),
+                score: search_result.ranking_score,
+                highlight: options
+                    .highlight
+                    .as_ref()
+                    .map(|_| LegalDocumentHighlight::from_search_result(search_result)),
+            })
+            .collect();
+        Ok(Some(LegalDocumentSearchResults {
+            hits,
+            offset: options.offset,
+            limit: options.limit,
+            estimated_total_hits: search_result.estimated_total_hits,
+            facets: None,
+        }))
+    }
+
+    /// meilisearchに一回問い合わせる
+    async fn execute_search(
+        &self,
+        compiled_query: &CompiledQuery,
+        is_strict: bool,
+        limit: usize,
+        offset: usize,
+        options: &SearchOptions,
+    ) -> Result<SearchResults<LegalDocument>, LawscapeCoreError> {
+        let highlight = options.highlight.as_ref();
+        let facet_attributes = FACET_ATTRIBUTES.map(|(attribute, _)| attribute);
+        let sort_expressions = options
+            .sort
+            .iter()
+            .map(|s| s.to_sort_expression())
+            .collect::<Vec<_>>();
+        let sort_expressions = sort_expressions
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>();
+        let crop_attributes = highlight
+            .and_then(|h| h.crop_length)
+            .map(|crop_length| [(HIGHLIGHT_ATTRIBUTE, Some(crop_length))]);
+        let filter = match (
+            options.filter.to_filter_expression(),
+            &compiled_query.filter,
+        ) {
+            (Some(f1), Some(f2)) => Some(format!("({f1}) AND ({f2})")),
+            (f1, f2) => f1.or(f2.clone()),
+        };
+        let vector = match (options.semantic_ratio, &self.embedder) {
+            (Some(_), Some(embedder)) => Some(embedder.embed(&compiled_query.semantic_query)),
+            (Some(_), None) => {
+                return Err(LawscapeCoreError::InvalidParameter(
+                    "semantic search is not available".to_string(),
+                ));
+            }
+            (None, _) => None,
+        };
+        let index = self.meilisearch_client.index(REGISTORY_INDEX_NAME);
+        let mut query = index.search();
+        query
+            .with_query(&compiled_query.query)
+            .with_limit(limit)
+            .with_offset(offset)
+            .with_locales(&["jpn"])
+            .with_show_ranking_score(true)
+            .with_ranking_score_threshold(options.cancel_score);
+        if let Some(filter) = &filter {
+            query.with_filter(filter);
+        }
+        if let (Some(semantic_ratio), Some(vector)) = (options.semantic_ratio, &vector) {
+            query
+                .with_hybrid(EMBEDDER_NAME, semantic_ratio)
+                .with_vector(vector);
+        }
+        if is_strict {
+            // 演算子を使った検索式では全ての検索語を含むものだけを対象にする
+            query.with_matching_strategy(MatchingStrategies::ALL);
+        }
+        if compiled_query.is_text_only {
+            query.with_attributes_to_search_on(&[HIGHLIGHT_ATTRIBUTE]);
+        }
+        if !sort_expressions.is_empty() {
+            query.with_sort(&sort_expressions);
+        }
+        if options.facets {
+            query.with_facets(Selectors::Some(&facet_attributes));
+        }
+        if let Some(highlight) = highlight {
+            query
+                .with_attributes_to_highlight(Selectors::Some(&[HIGHLIGHT_ATTRIBUTE]))
+                .with_highlight_pre_tag(&highlight.pre_tag)
+                .with_highlight_post_tag(&highlight.post_tag)
+                .with_show_matches_position(true);
+            if let Some(crop_attributes) = &crop_attributes {
+                query
+                    .with_attributes_to_crop(Selectors::Some(crop_attributes))
+                    .with_crop_marker(&highlight.crop_marker);
+            }
+        }
+        query
+            .execute::<LegalDocument>()
+            .await
+            .map_err(|e| LawscapeCoreError::MeilisearchSearchError(Box::new(e)))
+    }
+
+    /// 検索用レジストリから値を取得する。`options.query_syntax`が`true`の場合はwordを検索式として解析する。
+    pub async fn search(
+        &self,
+        word: &str,
+        options: &SearchOptions,
+    ) -> Result<LegalDocumentSearchResults, LawscapeCoreError> {
+        let limit = options.limit;
+        let offset = options.offset;
+        let (compiled_queries, is_strict) = if options.query_syntax {
+            let expr = query::parse_query(word)?;
+            (query::compile_query(&expr)?, !expr.is_plain())
+        } else {
+            (vec![CompiledQuery::keyword(word)], false)
+        };
+        let (mut result, estimated_total_hits, facets) = if let [compiled_query] =
+            compiled_queries.as_slice()
+        {
+            let search_result = self
+                .execute_search(compiled_query, is_strict, limit, offset, options)
+                .await?;
+            let facets = search_result.facet_distribution.map(|distribution| {
+                FACET_ATTRIBUTES
+                    .iter()
+                    .filter_map(|(attribute, name)| {
+                        let counts = distribution.get(*attribute)?;
+                        Some((name.to_string(), top_facet_values(counts)))
+                    })
+                    .collect()
+            });
+            (
+                search_result.hits,
+                search_result.estimated_total_hits,
+                facets,
+            )
+        } else {
+            // ORを含む場合はそれぞれの問い合わせの結果をまとめてから切り出す
+            let mut merged: HashMap<String, SearchResult<LegalDocument>> = HashMap::new();
+            let mut estimated_total_hits: Option<usize> = None;
+            for compiled_query in compiled_queries.iter() {
+                let search_result = self
+                    .execute_search(compiled_query, is_strict, offset + limit, 0, options)
+                    .await?;
+                estimated_total_hits = estimated_total_hits.max(search_result.estimated_total_hits);
+                for hit in search_result.hits {
+                    let id = hit.result.get_id();
+                    let is_better = merged
+                        .get(&id)
+                        .is_none_or(|h| h.ranking_score < hit.ranking_score);
+                    if is_better {
+                        merged.insert(id, hit);
+                    }
+                }
+            }
+            let mut hits = merged.into_values().collect::<Vec<_>>();
+            hits.sort_by(|t1, t2| {
+                compare_by_sort(&options.sort, &t1.result, &t2.result)
+                    .then_with(|| {
+                        t2.ranking_score
+                            .partial_cmp(&t1.ranking_score)
+                            .unwrap_or(Ordering::Equal)
+                    })
+                    .then_with(|| t1.result.get_id().cmp(&t2.result.get_id()))
+            });
+            // 問い合わせごとの件数を足し合わせると重複して数えるため、
+            // 最も多い問い合わせの件数とまとめた件数の大きい方を総件数の下限として返す
+            let estimated_total_hits = estimated_total_hits.map(|total| total.max(hits.len()));
+            let hits = hits.into_iter().skip(offset).collect::<Vec<_>>();
+            // 件数の集計も重複して数えるため行わない
+            (hits, estimated_total_hits, None)
+        };
+        // 並び替えの指定がある場合はmeilisearchの返した順を保つ
+        if options.sort.is_empty() {
+            result.sort_by(|t1, t2| t2.ranking_score.partial_cmp(&t1.ranking_score).unwrap());
+        }
+        let document_list = result
+            .iter()
+            .take(limit)
+            .map(|search_result| LegalDocumentSearchResult {
+                document: search_result.clone().result,
+                score: search_result.ranking_score,
+                highlight: options
+                    .highlight
+                    .as_ref()
+                    .map(|_| LegalDocumentHighlight::from_search_result(search_result)),
+            })
+            .collect();
+        Ok(LegalDocumentSearchResults {
+            hits: document_list,
+            offset,
+            limit,
+            estimated_total_hits,
+            facets,
+        })
+    }
+}
+
+impl GraphSource for LegalDocumentsRegistory {
+    async fn neighbor_edges(
+        &self,
+        nodes: &[GraphNode],
+        traversal: &GraphTraversal,
+    ) -> Result<Vec<NeighborEdge>, LawscapeCoreError> {
+        let mut edges = Vec::new();
+        for chunk in nodes.chunks(GRAPH_BATCH_SIZE) {
+            let ids_of = |kind: GraphNodeKind| {
+                chunk
+                    .iter()
+                    .filter(|node| node.kind == kind)
+                    .map(|node| node.id.as_str())
+                    .collect::<Vec<&str>>()
+            };
+            let precedent_ids = ids_of(GraphNodeKind::Precedent);
+            for kind in [GraphNodeKind::Law, GraphNodeKind::Article] {
+                let ids = ids_of(kind);
+                if ids.is_empty() {
+                    continue;
+                }
+                if traversal.direction.is_outgoing() {
+                    self.outgoing_law_edges(&ids, kind, &mut edges).await?;
+                }
+                if traversal.direction.is_incoming() {
+                    self.incoming_law_edges(&ids, kind, traversal, &mut edges)
+                        .await?;
+                }
+            }
+            if precedent_ids.is_empty() {
+                continue;
+            }
+            if traversal.direction.is_outgoing() {
+                for precedent in self.find_precedents_by_ids(&precedent_ids).await? {
+                    let id = precedent.precedent_id();
+                    for key in precedent.statute_keys.iter() {
+                        edges.push(NeighborEdge::outgoing(
+                            id,
+                            key,
+                            GraphEdgeKind::Statute,
+                            GraphNodeKind::from_statute_key(key),
+                        ));
+                    }
+                    for cited_id in precedent.cited_precedent_ids.iter() {
+                        edges.push(NeighborEdge::outgoing(
+                            id,
+                            cited_id,
+                            GraphEdgeKind::Citation,
+                            GraphNodeKind::Precedent,
+                        ));
+                    }
+                }
+            }
+            if traversal.direction.is_incoming() && traversal.allows(GraphEdgeKind::Citation) {
+                let filter = format!(
+                    "type = \"Precedent\" AND cited_precedent_ids IN [{}]",
+                    filter_value_list(precedent_ids.iter().copied())
+                );
+                self.push_citing_precedents(
+                    &precedent_ids,
+                    &filter,
+                    GraphEdgeKind::Citation,
+                    &mut edges,
+                )
+                .await?;
+            }
+        }
+        Ok(edges)
+    }
+
+    async fn nodes(
+        &self,
+        ids: &[(String, GraphNodeKind)],
+    ) -> Result<Vec<GraphNode>, LawscapeCoreError> {
+        let mut nodes = Vec::new();
+        for chunk in ids.chunks(GRAPH_BATCH_SIZE) {
+            let ids_of = |kind: GraphNodeKind| {
+                chunk
+                    .iter()
+                    .filter(|(_, k)| *k == kind)
+                    .map(|(id, _)| id.as_str())
+                    .collect::<Vec<&str>>()
+            };
+            let law_ids = ids_of(GraphNodeKind::Law);
+            if !law_ids.is_empty() {
+                let filter = format!(
+                    "{CURRENT_LAW_FILTER} AND position = 0 AND law_id IN [{}]",
+                    filter_value_list(law_ids.iter().copied())
+                );
+                let mut law_list = self.fetch_laws(&filter).await?;
+                law_list.sort_by_key(|l| l.law_id.to_string());
+                law_list.dedup_by(|l1, l2| l1.law_id == l2.law_id);
+                nodes.extend(law_list.into_iter().map(|l| GraphNode {
+                    id: l.law_id.to_string(),
+                    label: l.name,
+                    kind: GraphNodeKind::Law,
+                }));
+            }
+            let article_ids = ids_of(GraphNodeKind::Article);
+            if !article_ids.is_empty() {
+                let mut article_list = self
+                    .fetch_laws(&Self::article_filter(&article_ids))
+                    .await?
+                    .into_iter()
+                    .map(|l| GraphNode {
+                        id: citation::statute_key(&l.law_id.to_string(), Some(&l.index.article)),
+                        label: format!("{}{}", l.name, citation::article_label(&l.index.article)),
+                        kind: GraphNodeKind::Article,
+                    })
+                    .collect::<Vec<_>>();
+                article_list.sort_by(|n1, n2| n1.id.cmp(&n2.id));
+                article_list.dedup_by(|n1, n2| n1.id == n2.id);
+                nodes.extend(article_list);
+            }
+            let precedent_ids = ids_of(GraphNodeKind::Precedent);
+            if !precedent_ids.is_empty() {
+                nodes.extend(
+                    self.find_precedents_by_ids(&precedent_ids)
+                        .await?
+                        .into_iter()
+                        .map(|p| GraphNode {
+                            id: p.precedent_id().to_string(),
+                            label: p.info.case_name,
+                            kind: GraphNodeKind::Precedent,
+                        }),
+                );
+            }
+        }
+        Ok(nodes)
+    }
+}
+
+/// 集計結果から件数の多い順に`SEARCH_FACET_MAX_VALUES`個の値を選ぶ
+fn top_facet_values(counts: &HashMap<String, usize>) -> BTreeMap<String, usize> {
+    let mut counts = counts.iter().collect::<Vec<_>>();
+    counts.sort_by(|(v1, c1), (v2, c2)| c2.cmp(c1).then_with(|| v1.cmp(v2)));
+    counts
+        .into_iter()
+        .take(SEARCH_FACET_MAX_VALUES)
+        .map(|(value, count)| (value.clone(), *count))
+        .collect()
+}
+
+/// ハイライトやマッチ箇所の切り出しの対象とする属性
+const HIGHLIGHT_ATTRIBUTE: &str = "text";
+
+/// 文書の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LegalDocumentType {
+    Law,
+    Precedent,
+}
+
+impl LegalDocumentType {
+    /// 登録されている文書の`type`属性の値
+    fn type_name(&self) -> &'static str {
+        match self {
+            LegalDocumentType::Law => "Law",
+            LegalDocumentType::Precedent => "Precedent",
+        }
+    }
+
+    /// 入力補完の候補の`type`属性の値
+    fn suggestion_type_name(&self) -> &'static str {
+        match self {
+            LegalDocumentType::Law => "law",
+            LegalDocumentType::Precedent => "precedent",
+        }
+    }
+}
+
+impl FromStr for LegalDocumentType {
+    type Err = LawscapeCoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "law" => Ok(LegalDocumentType::Law),
+            "precedent" => Ok(LegalDocumentType::Precedent),
+            _ => Err(LawscapeCoreError::InvalidParameter(format!(
+                "unknown document type: {s}"
+            ))),
+        }
+    }
+}
+
+/// 入力補完の候補
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    /// 候補ごとのID
+    pub id: String,
+    #[serde(rename = "type")]
+    pub document_type: LegalDocumentType,
+    /// 法令名、または判例の事件名
+    pub name: String,
+    /// 判例の事件番号など、名前以外に補完の対象とする表記
+    pub keywords: Vec<String>,
+    /// 法令の場合は法令ID、判例の場合は文書のID
+    pub target_id: String,
+}
+
+impl Suggestion {
+    /// 法令名の候補を生成する
+    pub fn from_law(law_id: &LawId, name: &str) -> Self {
+        Self {
+            id: format!("law_{law_id}"),
+            document_type: LegalDocumentType::Law,
+            name: name.to_string(),
+            keywords: Vec::new(),
+            target_id: law_id.to_string(),
+        }
+    }
+
+    /// 判例の事件名の候補を生成する
+    pub fn from_precedent(precedent: &Precedent) -> Self {
+        Self {
+            id: format!("precedent_{}", precedent.id),
+            document_type: LegalDocumentType::Precedent,
+            name: precedent.info.case_name.clone(),
+            keywords: vec![
+                precedent.info.lawsuit_id.to_string(),
+                precedent.info.case_number.clone(),
+            ],
+            target_id: precedent.id.clone(),
+        }
+    }
+}
+
+/// 重複を取り除いた後も`limit`件を返せるよう、meilisearchから多めに取得する倍率
+const SUGGESTION_CANDIDATES_RATE: usize = 3;
+
+/// meilisearchから取得した候補を並べ替えて`limit`件に絞る。
+/// 名前かキーワードが`prefix`で始まる候補を先にし、それ以外はmeilisearchの順位を保つ。
+/// 種類・名前・キーワードが同じで利用者から区別できない候補は、先に現れたものだけを残す。
+fn select_suggestions(candidates: Vec<Suggestion>, prefix: &str, limit: usize) -> Vec<Suggestion> {
+    let mut seen = HashSet::new();
+    let mut unique = candidates
+        .into_iter()
+        .filter(|s| {
+            seen.insert((
+                s.document_type.suggestion_type_name(),
+                s.name.clone(),
+                s.keywords.clone(),
+            ))
+        })
+        .collect::<Vec<_>>();
+    // 安定ソートなので、同じ区分の中ではmeilisearchの順位が保たれる
+    unique.sort_by_key(|s| {
+        !(s.name.starts_with(prefix) || s.keywords.iter().any(|k| k.starts_with(prefix)))
+    });
+    unique.truncate(limit);
+    unique
+}
+
+/// 検索結果の絞り込み条件
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchFilter {
+    /// 文書の種類
+    pub document_type: Option<LegalDocumentType>,
+    /// 法令ID。空の場合は絞り込まない
+    pub law_ids: Vec<String>,
+    /// 判例の裁判所名。空の場合は絞り込まない
+    pub court_names: Vec<String>,
+    /// 判例の判決日の西暦での年。空の場合は絞り込まない
+    pub years: Vec<usize>,
+    /// 判決文の区分。空の場合は絞り込まない
+    pub sections: Vec<PrecedentSectionKind>,
+    /// 法令の種類。空の場合は絞り込まない
+    pub law_types: Vec<LawType>,
+    /// 判例が参照している法令の条文。`citation::statute_key`で求めた値で指定する。空の場合は絞り込まない
+    pub statutes: Vec<String>,
+    /// 廃止された法令も含めるかどうか
+    pub include_repealed: bool,
+    /// まだ施行されていない版の法令も含めるかどうか
+    pub include_future: bool,
+    /// 与えるとこの日（`yyyymmdd`の形の数）より後に施行された版の法令と、後に出された判例を除く
+    #[serde(skip)]
+    pub as_of: Option<usize>,
+}
+
+impl SearchFilter {
+    /// meilisearchのフィルタ式に変換する。条件が無い場合は`None`
+    pub fn to_filter_expression(&self) -> Option<String> {
+        let mut conditions = Vec::new();
+        if let Some(document_type) = self.document_type {
+            conditions.push(format!("type = \"{}\"", document_type.type_name()));
+        }
+        let to_list = |values: &[String]| filter_value_list(values.iter().map(|v| v.as_str()));
+        if !self.law_ids.is_empty() {
+            conditions.push(format!("law_id IN [{}]", to_list(&self.law_ids)));
+        }
+        if !self.court_names.is_empty() {
+            conditions.push(format!(
+                "info.court_name IN [{}]",
+                to_list(&self.court_names)
+            ));
+        }
+        if !self.years.is_empty() {
+            let years = self
+                .years
+                .iter()
+                .map(|y| y.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            conditions.push(format!("year IN [{years}]"));
+        }
+        if !self.sections.is_empty() {
+            let sections = self
+                .sections
+                .iter()
+                .map(|s| format!("\"{}\"", s.name()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            conditions.push(format!("section.kind IN [{sections}]"));
+        }
+        if !self.law_types.is_empty() {
+            let law_types = self
+                .law_types
+                .iter()
+                .map(|t| format!("\"{}\"", t.name()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            conditions.push(format!("law_type IN [{law_types}]"));
+        }
+        if !self.statutes.is_empty() {
+            conditions.push(format!("statute_keys IN [{}]", to_list(&self.statutes)));
+        }
+        let excluded_statuses = [
+            (LawStatus::Repealed, self.include_repealed),
+            (LawStatus::NotYetEffective, self.include_future),
+        ]
+        .iter()
+        .filter(|(_, is_included)| !is_included)
+        .map(|(status, _)| format!("\"{}\"", status.name()))
+        .collect::<Vec<_>>();
+        // 施行状況を持たない判例や古い文書は除かれないようにNOTを使う
+        if !excluded_statuses.is_empty() {
+            conditions.push(format!("NOT status IN [{}]", excluded_statuses.join(", ")));
+        }
+        // 日付を持たない古い文書は除かれないようにNOTを使う
+        if let Some(as_of) = self.as_of {
+            conditions.push(format!("NOT date_number > {as_of}"));
+        }
+        if conditions.is_empty() {
+            None
+        } else {
+            Some(conditions.join(" AND "))
+        }
+    }
+}
+
+/// 検索時の設定
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    /// 取得する最大件数
+    pub limit: usize,
+    /// 何件目から取得するか
+    pub offset: usize,
+    /// 打ち切り値
+    pub cancel_score: f64,
+    /// 与えると本文のマッチ箇所の情報も取得する
+    pub highlight: Option<HighlightOptions>,
+    pub filter: SearchFilter,
+    /// 文書の種類や裁判所ごとの件数を集計するかどうか
+    pub facets: bool,
+    /// 並び替えの条件。先頭のものから優先する。空の場合はスコアの順
+    pub sort: Vec<SearchSort>,
+    /// 与えると埋め込みベクトルを使ったハイブリッド検索を行う。
+    /// 0に近いほどキーワード検索を、1に近いほどベクトル検索を重視する
+    pub semantic_ratio: Option<f32>,
+    /// 検索語を`query::parse_query`で検索式として解析するかどうか。
+    /// `false`の場合は検索語をそのままmeilisearchに渡す
+    pub query_syntax: bool,
+}
+
+/// 検索結果の並び替えに使う値
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// 判例の判決日
+    Date,
+    /// 判例の裁判所の審級
+    CourtRank,
+}
+
+impl SortKey {
+    /// 登録されている文書の属性名
+    fn attribute(&self) -> &'static str {
+        match self {
+            SortKey::Date => "date_number",
+            SortKey::CourtRank => "court_rank",
+        }
+    }
+
+    /// 文書から並び替えに使う値を取り出す。法令の場合は`None`
+    fn value(&self, document: &LegalDocument) -> Option<usize> {
+        match (self, document) {
+            (SortKey::Date, LegalDocument::Precedent(p)) => p.date_number,
+            (SortKey::CourtRank, LegalDocument::Precedent(p)) => Some(p.court_rank),
+            (_, LegalDocument::Law(_)) => None,
+        }
+    }
+}
+
+/// 並び替えの向き
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// 並び替えの条件。`date:desc`のような文字列で表す
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SearchSort {
+    pub key: SortKey,
+    pub direction: SortDirection,
+}
+
+impl SearchSort {
+    /// meilisearchの並び替えの式に変換する
+    fn to_sort_expression(self) -> String {
+        let direction = match self.direction {
+            SortDirection::Asc => "asc",
+            SortDirection::Desc => "desc",
+        };
+        format!("{}:{direction}", self.key.attribute())
+    }
+
+    /// 文書の並び替えに使う比較。値を持たない文書は末尾に並ぶ
+    fn compare(&self, d1: &LegalDocument, d2: &LegalDocument) -> Ordering {
+        self.compare_values(self.key.value(d1), self.key.value(d2))
+    }
+
+    /// 並び替えに使う値の比較。値を持たないものは向きに関わらず末尾に並ぶ
+    fn compare_values(&self, v1: Option<usize>, v2: Option<usize>) -> Ordering {
+        match (v1, v2) {
+            (Some(v1), Some(v2)) => match self.direction {
+                SortDirection::Asc => v1.cmp(&v2),
+                SortDirection::Desc => v2.cmp(&v1),
+            },
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+impl FromStr for SearchSort {
+    type Err = LawscapeCoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, direction) = s.split_once(':').unwrap_or((s, "desc"));
+        let key = match key {
+            "date" => SortKey::Date,
+            "court_rank" => SortKey::CourtRank,
+            _ => {
+                return Err(LawscapeCoreError::InvalidParameter(format!(
+                    "unknown sort key: {key}"
+                )));
+            }
+        };
+        let direction = match direction {
+            "asc" => SortDirection::Asc,
+            "desc" => SortDirection::Desc,
+            _ => {
+                return Err(LawscapeCoreError::InvalidParameter(format!(
+                    "unknown sort direction: {direction}"
+                )));
+            }
+        };
+        Ok(SearchSort { key, direction })
+    }
+}
+
+impl TryFrom<String> for SearchSort {
+    type Error = LawscapeCoreError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<SearchSort> for String {
+    fn from(sort: SearchSort) -> Self {
+        let key = match sort.key {
+            SortKey::Date => "date",
+            SortKey::CourtRank => "court_rank",
+        };
+        let direction = match sort.direction {
+            SortDirection::Asc => "asc",
+            SortDirection::Desc => "desc",
+        };
+        format!("{key}:{direction}")
+    }
+}
+
+/// 並び替えの条件を順に適用して比較する
+fn compare_by_sort(sort: &[SearchSort], d1: &LegalDocument, d2: &LegalDocument) -> Ordering {
+    sort.iter().fold(Ordering::Equal, |ordering, s| {
+        ordering.then_with(|| s.compare(d1, d2))
+    })
+}
+
+/// 検索結果のハイライトの設定
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HighlightOptions {
+    /// マッチ箇所の前に挿入するタグ
+    pub pre_tag: String,
+    /// マッチ箇所の後に挿入するタグ
+    pub post_tag: String,
+    /// マッチ箇所の周辺を切り出す際の語数。`None`の場合は本文全体
+    pub crop_length: Option<usize>,
+    /// 切り出した箇所の前後に付ける記号
+    pub crop_marker: String,
+}
+
+impl Default for HighlightOptions {
+    fn default() -> Self {
+        Self {
+            pre_tag: "<em>".to_string(),
+            post_tag: "</em>".to_string(),
+            crop_length: None,
+            crop_marker: "…".to_string(),
+        }
+    }
+}
+
+/// 本文中のマッチ箇所。位置と長さはバイト単位
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchPosition {
+    pub start: usize,
+    pub length: usize,
+}
+
+/// 検索結果のハイライト情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegalDocumentHighlight {
+    /// ハイライト用のタグを挿入した本文、またはマッチ箇所の周辺を切り出したもの
+    pub snippet: Option<String>,
+    /// 本文中のマッチ箇所
+    pub matches: Vec<MatchPosition>,
+}
+
+impl LegalDocumentHighlight {
+    fn from_search_result(search_result: &SearchResult<LegalDocument>) -> Self {
+        let snippet = search_result
+            .formatted_result
+            .as_ref()
+            .and_then(|formatted| formatted.get(HIGHLIGHT_ATTRIBUTE))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let matches = search_result
+            .matches_position
+            .as_ref()
+            .and_then(|positions| positions.get(HIGHLIGHT_ATTRIBUTE))
+            .map(|ranges| {
+                ranges
+                    .iter()
+                    .map(|range| MatchPosition {
+                        start: range.start,
+                        length: range.length,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { snippet, matches }
+    }
+}
+
+/// 条文を参照している判例
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatutePrecedent {
+    /// 判決全体のID
+    pub precedent_id: String,
+    pub info: PrecedentInfo,
+    /// 指定した条文への参照
+    pub statutes: Vec<PrecedentStatute>,
+}
+
+/// 判例を引用している判例
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CitingPrecedent {
+    /// 判決全体のID
+    pub precedent_id: String,
+    pub info: PrecedentInfo,
+}
+
+/// 判例の引用関係
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrecedentCitations {
+    /// 判決全体のID
+    pub precedent_id: String,
+    /// 引用している判例
+    pub cites: Vec<CitedPrecedent>,
+    /// 引用されている判例
+    pub cited_by: Vec<CitingPrecedent>,
+}
+
+/// 引用の解決結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CitationResolution {
+    pub citation: Citation,
+    /// 法令名と条番号が一致した条文
+    pub matches: Vec<Law>,
+    /// 法令名が完全には一致しなかった候補
+    pub candidates: Vec<Law>,
+    /// 項や号が指定された場合の、一致した条文の中の該当箇所
+    pub provisions: Vec<LocatedProvision>,
+}
+
+/// 登録されている文書と対応付けられなかった参照の確からしさ
+const UNRESOLVED_CONFIDENCE: f64 = 0.2;
+/// 略称から法令名を補った場合に確からしさに掛ける値
+const ABBREVIATION_CONFIDENCE_RATE: f64 = 0.9;
+/// 「同法」のように直前の参照から法令名を補った場合に確からしさに掛ける値
+const INFERRED_CONFIDENCE_RATE: f64 = 0.8;
+
+/// 文章中で見つかった参照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum AnnotationReference {
+    Statute(StatuteReference),
+    Precedent(PrecedentReference),
+}
+
+impl AnnotationReference {
+    pub fn span(&self) -> citation::TextSpan {
+        match self {
+            AnnotationReference::Statute(s) => s.span,
+            AnnotationReference::Precedent(p) => p.span,
+        }
+    }
+}
+
+/// 文章中の参照と対応する文書
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    pub reference: AnnotationReference,
+    /// 参照している法令のID
+    pub law_id: Option<String>,
+    /// 参照している文書のID
+    pub document_ids: Vec<String>,
+    /// 対応付けの確からしさ。0から1の値をとる
+    pub confidence: f64,
+}
+
+/// 検索結果とページ送りのための情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegalDocumentSearchResults {
+    pub hits: Vec<LegalDocumentSearchResult>,
+    pub offset: usize,
+    pub limit: usize,
+    /// meilisearchによる推定総件数。区分ごとに分けて登録した判例は判決ごとに一件と数える。
+    /// ORを含む検索式の場合は、
+    /// 問い合わせごとの件数の最大値と取得した件数の大きい方で、実際の件数より少ないことがある
+    pub estimated_total_hits: Option<usize>,
+    /// 属性ごとの値とその件数。判決ごとにまとめる前の文書で数えるため、
+    /// 区分ごとに分けて登録した判例は一致した区分の数だけ数える。
+    /// ORを含む検索式の場合は集計しない
+    pub facets: Option<BTreeMap<String, BTreeMap<String, usize>>>,
+}
+
+impl LegalDocumentSearchResults {
+    /// 次のページを取得するためのoffset。次のページが無い場合は`None`
+    pub fn next_offset(&self) -> Option<usize> {
+        next_offset(self.offset, self.hits.len(), self.estimated_total_hits)
+    }
+}
+
+/// `offset`から`hits`件を取得した後の次のページのoffset。総件数に達した場合や結果が空の場合は`None`
+fn next_offset(offset: usize, hits: usize, estimated_total_hits: Option<usize>) -> Option<usize> {
+    let next = offset + hits;
+    match estimated_total_hits {
+        Some(total) if next < total && hits != 0 => Some(next),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegalDocumentSearchResult {
+    pub score: Option<f64>,
+    pub document: LegalDocument,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<LegalDocumentHighlight>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegalDocumentDependencies {
+    /// 同じ法令に含まれる法令文書など。判例の場合は通常一つ。
+    pub contents: Vec<LegalDocumentSearchResult>,
+    /// 参照している法令文書
+    pub parents: Vec<String>,
+    /// 参照されている法令文書
+    pub children: Vec<String>,
+    /// 参照している法令文書のうち、委任元にあたるもの
+    #[serde(default)]
+    pub delegating: Vec<String>,
+    /// 参照されている法令文書のうち、委任先にあたるもの
+    #[serde(default)]
+    pub delegated: Vec<String>,
+}
+
+pub fn analyze_search_result_dependencies(
+    legal_documents: &[LegalDocumentSearchResult],
+) -> HashMap<String, LegalDocumentDependencies> {
+    let mut id_list = legal_documents
+        .iter()
+        .map(|d| d.document.get_group_id())
+        .collect::<Vec<String>>();
+    id_list.sort();
+    id_list.dedup();
+    let mut contents_list = Vec::new();
+    for id in id_list.iter() {
+        let documents = legal_documents
+            .iter()
+            .filter(|d| &d.document.get_group_id() == id)
+            .cloned()
+            .collect::<Vec<_>>();
+        let document = documents.first().map(|d| d.document.clone());
+        let name = match document {
+            Some(LegalDocument::Law(l)) => Some(l.name.clone()),
+            _ => None,
+        };
+        contents_list.push((id, name, documents.clone()));
+    }
+    let mut paretns_list: Vec<Vec<String>> = vec![Vec::new(); id_list.len()];
+    let mut children_list: Vec<Vec<String>> = vec![Vec::new(); id_list.len()];
+    let mut delegating_list: Vec<Vec<String>> = vec![Vec::new(); id_list.len()];
+    let mut delegated_list: Vec<Vec<String>> = vec![Vec::new(); id_list.len()];
+    for (i, (id, name, _)) in contents_list.iter().enumerate() {
+        if let Some(name) = name {
+            for (j, (id2, name2, documents2)) in contents_list.iter().enumerate() {
+                if id == id2 {
+                    continue;
+                };
+                // 法令名称が含まれるかどうかの判定
+                let mut is_contains = false;
+                if let Some(name2) = name2 {
+                    is_contains = name2.contains(name);
+                }
+                for document2 in documents2.iter() {
+                    if is_contains {
+                        break;
+                    }
+                    is_contains = document2.document.get_text().contains(name);
                 }
                 if is_contains {
                     // idが親でid2が子にあたる
                     paretns_list[j].push(id.to_string());
                     children_list[i].push(id2.to_string());
+                    // 「〇〇法施行令」のように委任元にあたる場合は委任としても記録する
+                    let is_delegated = documents2.iter().any(|d| match &d.document {
+                        LegalDocument::Law(l) => l.delegated_by.as_deref() == Some(id.as_str()),
+                        LegalDocument::Precedent(_) => false,
+                    }) || name2
+                        .as_ref()
+                        .is_some_and(|name2| delegation::is_delegated_by(name2, name));
+                    if is_delegated {
+                        delegating_list[j].push(id.to_string());
+                        delegated_list[i].push(id2.to_string());
+                    }
                 }
             }
         }
@@ -188,8 +2633,513 @@ pub fn analyze_search_result_dependencies(
             contents: contents.clone(),
             parents: paretns_list[i].clone(),
             children: children_list[i].clone(),
+            delegating: delegating_list[i].clone(),
+            delegated: delegated_list[i].clone(),
         };
         document_dependencies.insert(id.to_string(), d);
     }
     document_dependencies
 }
+
+/// 検索結果のまとまりの並び順
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependenciesOrder {
+    /// まとまりの中で最も高いスコアの降順
+    #[default]
+    Score,
+    /// 法令IDの順。判例は末尾に並ぶ
+    LawId,
+    /// 判例の日付の新しい順。法令は末尾に並ぶ
+    Date,
+    /// 判例の裁判所の審級の高い順、同じ審級では日付の新しい順。法令は末尾に並ぶ
+    CourtRank,
+}
+
+impl FromStr for DependenciesOrder {
+    type Err = LawscapeCoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "score" => Ok(DependenciesOrder::Score),
+            "law_id" => Ok(DependenciesOrder::LawId),
+            "date" => Ok(DependenciesOrder::Date),
+            "court_rank" => Ok(DependenciesOrder::CourtRank),
+            _ => Err(LawscapeCoreError::InvalidParameter(format!(
+                "unknown order: {s}"
+            ))),
+        }
+    }
+}
+
+fn max_score(dependencies: &LegalDocumentDependencies) -> Option<f64> {
+    dependencies
+        .contents
+        .iter()
+        .filter_map(|d| d.score)
+        .max_by(|s1, s2| s1.partial_cmp(s2).unwrap_or(Ordering::Equal))
+}
+
+/// まとまりの先頭の文書の種類と、並び替えに使う値
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GroupSortKey {
+    Law,
+    /// 日付を持たない判例は`date_number`が`None`になる
+    Precedent {
+        court_rank: usize,
+        date_number: Option<usize>,
+    },
+    /// 文書を含まないまとまり
+    Empty,
+}
+
+/// まとまりを並び替えるための値。IDとスコアは同順位の場合に使う
+#[derive(Debug, Clone, Copy)]
+struct GroupSortEntry<'a> {
+    id: &'a str,
+    key: GroupSortKey,
+    score: Option<f64>,
+}
+
+impl<'a> GroupSortEntry<'a> {
+    fn new(id: &'a str, dependencies: &LegalDocumentDependencies) -> Self {
+        let key = match dependencies.contents.first().map(|d| &d.document) {
+            Some(LegalDocument::Law(_)) => GroupSortKey::Law,
+            Some(LegalDocument::Precedent(p)) => GroupSortKey::Precedent {
+                court_rank: p.court_rank,
+                date_number: p.date_number,
+            },
+            None => GroupSortKey::Empty,
+        };
+        Self {
+            id,
+            key,
+            score: max_score(dependencies),
+        }
+    }
+}
+
+fn compare_by_order(
+    e1: &GroupSortEntry,
+    e2: &GroupSortEntry,
+    order: DependenciesOrder,
+) -> Ordering {
+    use GroupSortKey::{Law, Precedent};
+    let ordering = match order {
+        DependenciesOrder::Score => Ordering::Equal,
+        DependenciesOrder::LawId => match (e1.key, e2.key) {
+            (Law, Law) => e1.id.cmp(e2.id),
+            (Law, _) => Ordering::Less,
+            (_, Law) => Ordering::Greater,
+            _ => Ordering::Equal,
+        },
+        // 日付を持たない判例は`None`が最小になるので、降順にすると判例の末尾に並ぶ
+        DependenciesOrder::Date => match (e1.key, e2.key) {
+            (
+                Precedent {
+                    date_number: date1, ..
+                },
+                Precedent {
+                    date_number: date2, ..
+                },
+            ) => date2.cmp(&date1),
+            (Precedent { .. }, _) => Ordering::Less,
+            (_, Precedent { .. }) => Ordering::Greater,
+            _ => Ordering::Equal,
+        },
+        DependenciesOrder::CourtRank => match (e1.key, e2.key) {
+            (
+                Precedent {
+                    court_rank: rank1,
+                    date_number: date1,
+                },
+                Precedent {
+                    court_rank: rank2,
+                    date_number: date2,
+                },
+            ) => rank2.cmp(&rank1).then_with(|| date2.cmp(&date1)),
+            (Precedent { .. }, _) => Ordering::Less,
+            (_, Precedent { .. }) => Ordering::Greater,
+            _ => Ordering::Equal,
+        },
+    };
+    // 同順位の場合はスコアの降順、最後にIDの順で並べて結果を一意に定める
+    ordering
+        .then_with(|| e2.score.partial_cmp(&e1.score).unwrap_or(Ordering::Equal))
+        .then_with(|| e1.id.cmp(e2.id))
+}
+
+/// 依存関係の解析結果を指定した順番に並べる。
+/// 法令のまとまりの中の条文は法令内での出現順に並べる。
+pub fn sort_search_result_dependencies(
+    dependencies: HashMap<String, LegalDocumentDependencies>,
+    order: DependenciesOrder,
+) -> Vec<LegalDocumentDependencies> {
+    let mut dependencies_list = dependencies
+        .into_iter()
+        .map(|(id, mut d)| {
+            d.contents
+                .sort_by(|c1, c2| match (&c1.document, &c2.document) {
+                    (LegalDocument::Law(l1), LegalDocument::Law(l2)) => {
+                        l1.position.cmp(&l2.position)
+                    }
+                    _ => Ordering::Equal,
+                });
+            (id, d)
+        })
+        .collect::<Vec<_>>();
+    dependencies_list.sort_by(|(id1, d1), (id2, d2)| {
+        compare_by_order(
+            &GroupSortEntry::new(id1, d1),
+            &GroupSortEntry::new(id2, d2),
+            order,
+        )
+    });
+    dependencies_list.into_iter().map(|(_, d)| d).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn law_with_remaining_article_is_in_force() {
+        let status = LawStatus::detect_from_texts(["削除", "この法律は、公布の日から施行する。"]);
+        assert_eq!(status, LawStatus::InForce);
+    }
+
+    #[test]
+    fn law_with_only_deleted_articles_is_repealed() {
+        let status = LawStatus::detect_from_texts(["削除", "  ", "削除\n"]);
+        assert_eq!(status, LawStatus::Repealed);
+    }
+
+    #[test]
+    fn law_without_articles_is_repealed() {
+        let status = LawStatus::detect_from_texts([]);
+        assert_eq!(status, LawStatus::Repealed);
+    }
+
+    #[test]
+    fn law_names_cache_is_shared_between_clones() {
+        let cache = LawNamesCache::default();
+        assert!(cache.get().is_none());
+        let table = Arc::new(LawNameTable::from_names(["民法".to_string()]));
+        cache.clone().set(table.clone());
+        assert!(Arc::ptr_eq(&cache.get().unwrap(), &table));
+    }
+
+    #[test]
+    fn top_facet_values_keeps_all_below_limit() {
+        let counts = HashMap::from([("a".to_string(), 1), ("b".to_string(), 3)]);
+        assert_eq!(
+            top_facet_values(&counts),
+            BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 3)])
+        );
+    }
+
+    #[test]
+    fn top_facet_values_prefers_counts_then_names() {
+        let mut counts = (0..=SEARCH_FACET_MAX_VALUES)
+            .map(|i| (format!("v{i:03}"), 1))
+            .collect::<HashMap<String, usize>>();
+        counts.insert("z".to_string(), 5);
+        let top = top_facet_values(&counts);
+        assert_eq!(top.len(), SEARCH_FACET_MAX_VALUES);
+        // 件数の多い値は名前の順に関わらず残る
+        assert_eq!(top.get("z"), Some(&5));
+        // 同じ件数の値は名前の順に残り、上限を超えた分は除かれる
+        assert!(top.contains_key("v000"));
+        assert!(top.contains_key(&format!("v{:03}", SEARCH_FACET_MAX_VALUES - 2)));
+        assert!(!top.contains_key(&format!("v{:03}", SEARCH_FACET_MAX_VALUES - 1)));
+        assert!(!top.contains_key(&format!("v{SEARCH_FACET_MAX_VALUES:03}")));
+    }
+
+    #[test]
+    fn search_sort_from_str() {
+        assert_eq!(
+            "date:asc".parse::<SearchSort>().unwrap(),
+            SearchSort {
+                key: SortKey::Date,
+                direction: SortDirection::Asc,
+            }
+        );
+        // 向きを省略した場合は降順
+        assert_eq!(
+            "court_rank".parse::<SearchSort>().unwrap(),
+            SearchSort {
+                key: SortKey::CourtRank,
+                direction: SortDirection::Desc,
+            }
+        );
+        assert!("dat:desc".parse::<SearchSort>().is_err());
+        assert!("date:down".parse::<SearchSort>().is_err());
+        assert_eq!(
+            String::from("court_rank:asc".parse::<SearchSort>().unwrap()),
+            "court_rank:asc"
+        );
+    }
+
+    #[test]
+    fn court_rank_by_court_name() {
+        assert_eq!(court_rank("最高裁判所第一小法廷"), 4);
+        assert_eq!(court_rank("大審院"), 4);
+        assert_eq!(court_rank("東京高等裁判所"), 3);
+        assert_eq!(court_rank("知的財産高等裁判所"), 3);
+        assert_eq!(court_rank("大阪地方裁判所"), 2);
+        assert_eq!(court_rank("横浜家庭裁判所"), 2);
+        assert_eq!(court_rank("東京簡易裁判所"), 1);
+        assert_eq!(court_rank("公害等調整委員会"), 0);
+    }
+
+    #[test]
+    fn search_sort_by_court_rank() {
+        let desc = "court_rank:desc".parse::<SearchSort>().unwrap();
+        let mut ranks = vec![Some(2), None, Some(4), Some(1), Some(3)];
+        ranks.sort_by(|r1, r2| desc.compare_values(*r1, *r2));
+        assert_eq!(ranks, vec![Some(4), Some(3), Some(2), Some(1), None]);
+        let asc = "court_rank:asc".parse::<SearchSort>().unwrap();
+        ranks.sort_by(|r1, r2| asc.compare_values(*r1, *r2));
+        // 値を持たない法令は昇順でも末尾に並ぶ
+        assert_eq!(ranks, vec![Some(1), Some(2), Some(3), Some(4), None]);
+    }
+
+    #[test]
+    fn next_offset_until_total_hits() {
+        assert_eq!(next_offset(0, 20, Some(50)), Some(20));
+        assert_eq!(next_offset(40, 9, Some(50)), Some(49));
+        assert_eq!(next_offset(40, 10, Some(50)), None);
+        // 推定総件数が実際より少ない場合も総件数を超えて進まない
+        assert_eq!(next_offset(40, 20, Some(50)), None);
+        assert_eq!(next_offset(0, 20, None), None);
+        // 結果が空の場合は同じoffsetを返し続けないようにする
+        assert_eq!(next_offset(60, 0, Some(100)), None);
+    }
+
+    fn law_entry(id: &str, score: Option<f64>) -> GroupSortEntry<'_> {
+        GroupSortEntry {
+            id,
+            key: GroupSortKey::Law,
+            score,
+        }
+    }
+
+    fn precedent_entry(
+        id: &str,
+        court_rank: usize,
+        date_number: Option<usize>,
+    ) -> GroupSortEntry<'_> {
+        GroupSortEntry {
+            id,
+            key: GroupSortKey::Precedent {
+                court_rank,
+                date_number,
+            },
+            score: None,
+        }
+    }
+
+    /// 比較の結果に従って並べたIDの一覧
+    fn sorted_ids<'a>(
+        mut entries: Vec<GroupSortEntry<'a>>,
+        order: DependenciesOrder,
+    ) -> Vec<&'a str> {
+        entries.sort_by(|e1, e2| compare_by_order(e1, e2, order));
+        entries.iter().map(|e| e.id).collect()
+    }
+
+    fn mixed_entries() -> Vec<GroupSortEntry<'static>> {
+        vec![
+            precedent_entry("p_old_supreme", 4, Some(19700101)),
+            law_entry("law_b", Some(0.2)),
+            precedent_entry("p_no_date", 4, None),
+            precedent_entry("p_new_district", 1, Some(20200101)),
+            law_entry("law_a", Some(0.9)),
+            precedent_entry("p_new_supreme", 4, Some(20100101)),
+        ]
+    }
+
+    #[test]
+    fn order_by_score_then_id() {
+        assert_eq!(
+            sorted_ids(mixed_entries(), DependenciesOrder::Score),
+            vec![
+                "law_a",
+                "law_b",
+                "p_new_district",
+                "p_new_supreme",
+                "p_no_date",
+                "p_old_supreme"
+            ]
+        );
+    }
+
+    #[test]
+    fn order_by_law_id_puts_precedents_last() {
+        let ids = sorted_ids(mixed_entries(), DependenciesOrder::LawId);
+        assert_eq!(ids[..2], ["law_a", "law_b"]);
+    }
+
+    #[test]
+    fn order_by_date_puts_undated_precedents_and_laws_last() {
+        assert_eq!(
+            sorted_ids(mixed_entries(), DependenciesOrder::Date),
+            vec![
+                "p_new_district",
+                "p_new_supreme",
+                "p_old_supreme",
+                "p_no_date",
+                "law_a",
+                "law_b"
+            ]
+        );
+    }
+
+    #[test]
+    fn order_by_court_rank_then_date() {
+        assert_eq!(
+            sorted_ids(mixed_entries(), DependenciesOrder::CourtRank),
+            vec![
+                "p_new_supreme",
+                "p_old_supreme",
+                "p_no_date",
+                "p_new_district",
+                "law_a",
+                "law_b"
+            ]
+        );
+    }
+
+    #[test]
+    fn dependencies_order_from_str() {
+        assert_eq!(
+            "court_rank".parse::<DependenciesOrder>().unwrap(),
+            DependenciesOrder::CourtRank
+        );
+        assert!("dat".parse::<DependenciesOrder>().is_err());
+    }
+
+    fn suggestion(
+        id: &str,
+        document_type: LegalDocumentType,
+        name: &str,
+        keywords: &[&str],
+    ) -> Suggestion {
+        Suggestion {
+            id: id.to_string(),
+            document_type,
+            name: name.to_string(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            target_id: id.to_string(),
+        }
+    }
+
+    fn suggestion_ids(suggestions: &[Suggestion]) -> Vec<&str> {
+        suggestions.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[test]
+    fn select_suggestions_prefers_prefix_matches() {
+        let candidates = vec![
+            suggestion(
+                "law_1",
+                LegalDocumentType::Law,
+                "行政手続における個人情報の保護に関する法律",
+                &[],
+            ),
+            suggestion(
+                "law_2",
+                LegalDocumentType::Law,
+                "個人情報の保護に関する法律",
+                &[],
+            ),
+            suggestion(
+                "law_3",
+                LegalDocumentType::Law,
+                "独立行政法人等の保有する個人情報の保護に関する法律",
+                &[],
+            ),
+            suggestion(
+                "law_4",
+                LegalDocumentType::Law,
+                "個人情報の保護に関する法律施行令",
+                &[],
+            ),
+        ];
+        let suggestions = select_suggestions(candidates, "個人情報", 10);
+        assert_eq!(
+            suggestion_ids(&suggestions),
+            vec!["law_2", "law_4", "law_1", "law_3"]
+        );
+    }
+
+    #[test]
+    fn select_suggestions_matches_keywords() {
+        let candidates = vec![
+            suggestion(
+                "precedent_1",
+                LegalDocumentType::Precedent,
+                "損害賠償請求事件",
+                &["1", "平成10(オ)100"],
+            ),
+            suggestion(
+                "precedent_2",
+                LegalDocumentType::Precedent,
+                "所有権確認請求事件",
+                &["2", "平成20(受)200"],
+            ),
+        ];
+        let suggestions = select_suggestions(candidates, "平成20", 10);
+        assert_eq!(
+            suggestion_ids(&suggestions),
+            vec!["precedent_2", "precedent_1"]
+        );
+    }
+
+    #[test]
+    fn select_suggestions_removes_indistinguishable_duplicates() {
+        let candidates = vec![
+            suggestion("law_1", LegalDocumentType::Law, "民法", &[]),
+            suggestion("law_2", LegalDocumentType::Law, "民法", &[]),
+            suggestion("precedent_1", LegalDocumentType::Precedent, "民法", &[]),
+            suggestion(
+                "precedent_2",
+                LegalDocumentType::Precedent,
+                "損害賠償請求事件",
+                &["2", "平成10(オ)100"],
+            ),
+            suggestion(
+                "precedent_3",
+                LegalDocumentType::Precedent,
+                "損害賠償請求事件",
+                &["3", "平成11(オ)200"],
+            ),
+        ];
+        let suggestions = select_suggestions(candidates, "民法", 10);
+        assert_eq!(
+            suggestion_ids(&suggestions),
+            vec!["law_1", "precedent_1", "precedent_2", "precedent_3"]
+        );
+    }
+
+    #[test]
+    fn select_suggestions_truncates_to_limit() {
+        let candidates = vec![
+            suggestion(
+                "law_1",
+                LegalDocumentType::Law,
+                "民事訴訟費用等に関する法律",
+                &[],
+            ),
+            suggestion("law_2", LegalDocumentType::Law, "民法", &[]),
+            suggestion("law_3", LegalDocumentType::Law, "民法", &[]),
+            suggestion("law_4", LegalDocumentType::Law, "民事訴訟法", &[]),
+            suggestion("law_5", LegalDocumentType::Law, "民事執行法", &[]),
+        ];
+        let suggestions = select_suggestions(candidates.clone(), "民", 3);
+        assert_eq!(
+            suggestion_ids(&suggestions),
+            vec!["law_1", "law_2", "law_4"]
+        );
+        assert!(select_suggestions(candidates, "民", 0).is_empty());
+    }
+}
@@ -0,0 +1,304 @@
+//! 基準日に施行されている法令の版を選ぶ
+
+use crate::LawscapeCoreError;
+use jplaw_data_types::law::{Date, LawPatchInfo};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// 基準日と同じ日に施行される版を、基準日に施行されているものとして扱うかどうか
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateBoundary {
+    /// 基準日に施行される版を含める
+    #[default]
+    Inclusive,
+    /// 基準日に施行される版を含めず、前日までに施行された版から選ぶ
+    Exclusive,
+}
+
+impl FromStr for DateBoundary {
+    type Err = LawscapeCoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inclusive" => Ok(DateBoundary::Inclusive),
+            "exclusive" => Ok(DateBoundary::Exclusive),
+            _ => Err(LawscapeCoreError::InvalidParameter(format!(
+                "unknown date boundary: {s}"
+            ))),
+        }
+    }
+}
+
+/// 法令の版
+pub trait LawVersion {
+    /// 施行日
+    fn effective_date(&self) -> &Date;
+    /// 同じ日に施行される版の中から一つを選ぶために使う値
+    fn version_key(&self) -> String;
+}
+
+impl LawVersion for LawPatchInfo {
+    fn effective_date(&self) -> &Date {
+        &self.patch_date
+    }
+    fn version_key(&self) -> String {
+        self.to_file_path()
+    }
+}
+
+/// 施行日の順、同じ施行日では`version_key`の順に比べる
+fn compare_versions<T: LawVersion>(v1: &T, v2: &T) -> Ordering {
+    v1.effective_date()
+        .partial_cmp(v2.effective_date())
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| v1.version_key().cmp(&v2.version_key()))
+}
+
+/// 基準日までに施行されたかどうか
+fn is_in_force<T: LawVersion>(version: &T, date: &Date, boundary: DateBoundary) -> bool {
+    match boundary {
+        DateBoundary::Inclusive => version.effective_date() <= date,
+        DateBoundary::Exclusive => version.effective_date() < date,
+    }
+}
+
+/// 基準日に施行されている版を選ぶ。基準日までに施行された版のうち施行日が最も新しいものを選び、
+/// 同じ施行日の版が複数ある場合は`version_key`が最も大きいものを選ぶ。
+/// 施行されている版が無い場合は`None`を返す。
+pub fn select_in_force_version<'a, T: LawVersion>(
+    versions: &'a [T],
+    date: &Date,
+    boundary: DateBoundary,
+) -> Option<&'a T> {
+    versions
+        .iter()
+        .filter(|v| is_in_force(*v, date, boundary))
+        .max_by(|v1, v2| compare_versions(*v1, *v2))
+}
+
+/// 基準日より後に施行される版のうち、次に施行されるものを選ぶ。
+/// 同じ施行日の版が複数ある場合は`version_key`が最も大きいものを選ぶ。
+pub fn select_future_version<'a, T: LawVersion>(
+    versions: &'a [T],
+    date: &Date,
+    boundary: DateBoundary,
+) -> Option<&'a T> {
+    versions
+        .iter()
+        .filter(|v| !is_in_force(*v, date, boundary))
+        .min_by(|v1, v2| {
+            v1.effective_date()
+                .partial_cmp(v2.effective_date())
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| v2.version_key().cmp(&v1.version_key()))
+        })
+}
+
+/// 基準日に施行されている版が無かった法令
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingLawVersion {
+    pub law_id: String,
+    pub name: String,
+    /// 最も早く施行される版の施行日。版が一つも無い場合は`None`
+    pub earliest_effective_date: Option<Date>,
+}
+
+/// 法令の版の選択結果の報告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchSelectionReport {
+    /// 基準日
+    pub date: Date,
+    pub boundary: DateBoundary,
+    /// 版を選べた法令の数
+    pub selected_count: usize,
+    /// 基準日に施行されている版が無かった法令
+    pub missing: Vec<MissingLawVersion>,
+}
+
+impl PatchSelectionReport {
+    pub fn new(date: &Date, boundary: DateBoundary) -> Self {
+        Self {
+            date: date.clone(),
+            boundary,
+            selected_count: 0,
+            missing: Vec::new(),
+        }
+    }
+
+    /// 一つの法令について版を選び、結果を記録する
+    pub fn select<'a, T: LawVersion>(
+        &mut self,
+        law_id: &str,
+        name: &str,
+        versions: &'a [T],
+    ) -> Option<&'a T> {
+        let selected = select_in_force_version(versions, &self.date, self.boundary);
+        if selected.is_some() {
+            self.selected_count += 1;
+        } else {
+            let earliest_effective_date = versions
+                .iter()
+                .min_by(|v1, v2| compare_versions(*v1, *v2))
+                .map(|v| v.effective_date().clone());
+            self.missing.push(MissingLawVersion {
+                law_id: law_id.to_string(),
+                name: name.to_string(),
+                earliest_effective_date,
+            });
+        }
+        selected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestVersion {
+        date: Date,
+        key: &'static str,
+    }
+
+    impl LawVersion for TestVersion {
+        fn effective_date(&self) -> &Date {
+            &self.date
+        }
+        fn version_key(&self) -> String {
+            self.key.to_string()
+        }
+    }
+
+    fn version(y: usize, m: usize, d: usize, key: &'static str) -> TestVersion {
+        TestVersion {
+            date: Date::gen_from_ad(y, m, d),
+            key,
+        }
+    }
+
+    /// 2020年4月1日に制定され、2023年4月1日に2回、2025年4月1日に改正される法令
+    fn fixture() -> Vec<TestVersion> {
+        vec![
+            version(2023, 4, 1, "123AC_20230401_505AC0000000002"),
+            version(2020, 4, 1, "123AC_20200401_000000000000000"),
+            version(2025, 4, 1, "123AC_20250401_506AC0000000010"),
+            version(2023, 4, 1, "123AC_20230401_505AC0000000001"),
+        ]
+    }
+
+    #[test]
+    fn inclusive_selects_version_effective_on_date() {
+        let versions = fixture();
+        let selected = select_in_force_version(
+            &versions,
+            &Date::gen_from_ad(2025, 4, 1),
+            DateBoundary::Inclusive,
+        );
+        assert_eq!(
+            selected.map(|v| v.key),
+            Some("123AC_20250401_506AC0000000010")
+        );
+    }
+
+    #[test]
+    fn exclusive_skips_version_effective_on_date() {
+        let versions = fixture();
+        let selected = select_in_force_version(
+            &versions,
+            &Date::gen_from_ad(2025, 4, 1),
+            DateBoundary::Exclusive,
+        );
+        assert_eq!(
+            selected.map(|v| v.key),
+            Some("123AC_20230401_505AC0000000002")
+        );
+    }
+
+    #[test]
+    fn same_date_is_broken_by_version_key() {
+        let mut versions = fixture();
+        let date = Date::gen_from_ad(2024, 1, 1);
+        let selected =
+            select_in_force_version(&versions, &date, DateBoundary::Inclusive).map(|v| v.key);
+        // 並び順によらず同じ版が選ばれる
+        versions.reverse();
+        let selected_reversed =
+            select_in_force_version(&versions, &date, DateBoundary::Inclusive).map(|v| v.key);
+        assert_eq!(selected, Some("123AC_20230401_505AC0000000002"));
+        assert_eq!(selected, selected_reversed);
+    }
+
+    #[test]
+    fn no_version_before_enactment() {
+        let versions = fixture();
+        let selected = select_in_force_version(
+            &versions,
+            &Date::gen_from_ad(2020, 3, 31),
+            DateBoundary::Inclusive,
+        );
+        assert_eq!(selected, None);
+    }
+
+    #[test]
+    fn future_version_is_next_after_date() {
+        let versions = fixture();
+        let date = Date::gen_from_ad(2023, 4, 1);
+        let future = select_future_version(&versions, &date, DateBoundary::Inclusive);
+        assert_eq!(
+            future.map(|v| v.key),
+            Some("123AC_20250401_506AC0000000010")
+        );
+        // 施行前の版が複数ある場合は最も早く施行されるものを選ぶ
+        let future = select_future_version(
+            &versions,
+            &Date::gen_from_ad(2021, 1, 1),
+            DateBoundary::Inclusive,
+        );
+        assert_eq!(
+            future.map(|v| v.key),
+            Some("123AC_20230401_505AC0000000002")
+        );
+        let future = select_future_version(
+            &versions,
+            &Date::gen_from_ad(2023, 4, 1),
+            DateBoundary::Exclusive,
+        );
+        assert_eq!(
+            future.map(|v| v.key),
+            Some("123AC_20230401_505AC0000000002")
+        );
+        let future = select_future_version(
+            &versions,
+            &Date::gen_from_ad(2025, 4, 1),
+            DateBoundary::Inclusive,
+        );
+        assert_eq!(future, None);
+    }
+
+    #[test]
+    fn report_records_laws_without_version_in_force() {
+        let versions = fixture();
+        let mut report =
+            PatchSelectionReport::new(&Date::gen_from_ad(2021, 1, 1), DateBoundary::Inclusive);
+        assert!(report.select("123AC", "テスト法", &versions).is_some());
+        let empty: Vec<TestVersion> = Vec::new();
+        assert!(report.select("124AC", "空の法", &empty).is_none());
+        let mut report_before =
+            PatchSelectionReport::new(&Date::gen_from_ad(2019, 1, 1), DateBoundary::Inclusive);
+        assert!(
+            report_before
+                .select("123AC", "テスト法", &versions)
+                .is_none()
+        );
+
+        assert_eq!(report.selected_count, 1);
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].law_id, "124AC");
+        assert!(report.missing[0].earliest_effective_date.is_none());
+        assert_eq!(
+            report_before.missing[0].earliest_effective_date,
+            Some(Date::gen_from_ad(2020, 4, 1))
+        );
+    }
+}
@@ -0,0 +1,542 @@
+//! 判決文を主文・事実・理由などの区分に分け、判例が参照している法令の条文や判例を取り出す
+
+use crate::citation::{self, LawNameTable, PrecedentCitation, statute_key};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// 判決文の区分の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrecedentSectionKind {
+    /// 最初の見出しより前の、当事者などが書かれた部分
+    Preamble,
+    /// 主文
+    MainText,
+    /// 事実及び理由
+    FactsAndReasons,
+    /// 事実
+    Facts,
+    /// 理由
+    Reasons,
+    /// 補足意見
+    SupplementaryOpinion,
+    /// 反対意見
+    DissentingOpinion,
+    /// 意見
+    Opinion,
+}
+
+impl PrecedentSectionKind {
+    /// 登録されている文書の`section.kind`属性の値
+    pub fn name(&self) -> &'static str {
+        match self {
+            PrecedentSectionKind::Preamble => "preamble",
+            PrecedentSectionKind::MainText => "main_text",
+            PrecedentSectionKind::FactsAndReasons => "facts_and_reasons",
+            PrecedentSectionKind::Facts => "facts",
+            PrecedentSectionKind::Reasons => "reasons",
+            PrecedentSectionKind::SupplementaryOpinion => "supplementary_opinion",
+            PrecedentSectionKind::DissentingOpinion => "dissenting_opinion",
+            PrecedentSectionKind::Opinion => "opinion",
+        }
+    }
+
+    /// `name`で得られる名前から種類を求める
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            PrecedentSectionKind::Preamble,
+            PrecedentSectionKind::MainText,
+            PrecedentSectionKind::FactsAndReasons,
+            PrecedentSectionKind::Facts,
+            PrecedentSectionKind::Reasons,
+            PrecedentSectionKind::SupplementaryOpinion,
+            PrecedentSectionKind::DissentingOpinion,
+            PrecedentSectionKind::Opinion,
+        ]
+        .into_iter()
+        .find(|kind| kind.name() == name)
+    }
+}
+
+/// 見出しだけの行と区分の種類の対応。空白を取り除いてから比較する
+const SECTION_HEADINGS: &[(&str, PrecedentSectionKind)] = &[
+    ("主文", PrecedentSectionKind::MainText),
+    ("事実及び理由", PrecedentSectionKind::FactsAndReasons),
+    ("事実及理由", PrecedentSectionKind::FactsAndReasons),
+    ("事実", PrecedentSectionKind::Facts),
+    ("理由", PrecedentSectionKind::Reasons),
+];
+
+/// 判決文の区分
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrecedentSectionText {
+    pub kind: PrecedentSectionKind,
+    /// 見出しの行。前文の場合は空
+    pub title: String,
+    pub text: String,
+}
+
+/// 行が区分の見出しであればその種類を返す
+fn heading_kind(line: &str) -> Option<PrecedentSectionKind> {
+    let normalized = line
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    if let Some((_, kind)) = SECTION_HEADINGS.iter().find(|(h, _)| *h == normalized) {
+        return Some(*kind);
+    }
+    // 「裁判官〇〇〇〇の反対意見は，次のとおりである。」のような行から個別意見が始まる
+    if normalized.starts_with("裁判官") {
+        if normalized.contains("補足意見は") {
+            return Some(PrecedentSectionKind::SupplementaryOpinion);
+        } else if normalized.contains("反対意見は") {
+            return Some(PrecedentSectionKind::DissentingOpinion);
+        } else if normalized.contains("の意見は") {
+            return Some(PrecedentSectionKind::Opinion);
+        }
+    }
+    None
+}
+
+/// 判決文を区分に分ける。見出しが見つからない場合は空を返す。
+/// 見出しの行は`title`に入れ、区分をつなげると元の判決文に戻るよう`text`の先頭にも残す。
+pub fn split_precedent_sections(text: &str) -> Vec<PrecedentSectionText> {
+    let mut sections = Vec::new();
+    let mut current = PrecedentSectionText {
+        kind: PrecedentSectionKind::Preamble,
+        title: String::new(),
+        text: String::new(),
+    };
+    let mut has_heading = false;
+    for line in text.lines() {
+        if let Some(kind) = heading_kind(line) {
+            has_heading = true;
+            let section = PrecedentSectionText {
+                kind,
+                title: line.trim().to_string(),
+                text: line.to_string(),
+            };
+            let previous = std::mem::replace(&mut current, section);
+            sections.push(previous);
+            continue;
+        }
+        if !current.text.is_empty() || current.kind != PrecedentSectionKind::Preamble {
+            current.text.push('\n');
+        }
+        current.text.push_str(line);
+    }
+    sections.push(current);
+    if !has_heading {
+        return Vec::new();
+    }
+    sections
+        .into_iter()
+        .filter(|s| !s.text.trim().is_empty() || s.kind != PrecedentSectionKind::Preamble)
+        .collect()
+}
+
+/// 判例が参照している条文を見つけた箇所
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatuteSource {
+    /// 判例データの参照法条
+    ReferenceArticles,
+    /// 判決文
+    Text,
+}
+
+/// 判例が参照している法令の条文
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct PrecedentStatute {
+    /// 法令ID。登録されている法令と対応付けられなかった場合は`None`
+    pub law_id: Option<String>,
+    /// 略称を展開した法令名
+    pub law_name: String,
+    /// 条番号。「第三条の二」は`3_2`のように表す
+    pub article: Option<String>,
+    /// 項番号
+    pub paragraph: Option<usize>,
+    /// 号番号
+    pub item: Option<usize>,
+    pub source: StatuteSource,
+}
+
+impl PrecedentStatute {
+    /// 絞り込みに使う値。法令全体と条の二つで、法令IDと対応付けられなかった場合は空
+    pub fn keys(&self) -> Vec<String> {
+        let Some(law_id) = &self.law_id else {
+            return Vec::new();
+        };
+        let mut keys = vec![statute_key(law_id, None)];
+        if let Some(article) = &self.article {
+            keys.push(statute_key(law_id, Some(article)));
+        }
+        keys
+    }
+}
+
+/// 参照法条と判決文から、判例が参照している法令の条文を取り出す。
+/// 判決文からは条番号を伴う参照のみを取り出す。
+/// 同じ条文への参照は一つにまとめ、参照法条にあるものを先に並べる。
+pub fn extract_precedent_statutes(
+    reference_articles: Option<&str>,
+    text: &str,
+    law_names: &LawNameTable,
+) -> Vec<PrecedentStatute> {
+    let sources = reference_articles
+        .map(|s| (s, StatuteSource::ReferenceArticles))
+        .into_iter()
+        .chain([(text, StatuteSource::Text)]);
+    let mut statutes = Vec::new();
+    let mut seen = HashSet::new();
+    for (source_text, source) in sources {
+        let candidates = law_names.candidates(source_text);
+        for reference in citation::find_statute_references(source_text, &candidates) {
+            let law_id = law_names.resolve(&reference).cloned();
+            let Some(law_name) = reference.official_law_name else {
+                continue;
+            };
+            if source == StatuteSource::Text && reference.article.is_none() {
+                continue;
+            }
+            let key = (
+                law_name.clone(),
+                reference.article.clone(),
+                reference.paragraph,
+                reference.item,
+            );
+            if !seen.insert(key) {
+                continue;
+            }
+            statutes.push(PrecedentStatute {
+                law_id,
+                law_name,
+                article: reference.article,
+                paragraph: reference.paragraph,
+                item: reference.item,
+                source,
+            });
+        }
+    }
+    statutes
+}
+
+/// 判例が引用している判例
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct CitedPrecedent {
+    /// 登録されている判例のID。対応付けられなかった場合は`None`
+    pub precedent_id: Option<String>,
+    pub citation: PrecedentCitation,
+}
+
+/// 裁判所と判決日から登録されている判例を探すための表
+#[derive(Debug, Clone, Default)]
+pub struct PrecedentTable {
+    /// 判決日ごとの裁判所名と判例のID
+    table: HashMap<(usize, usize, usize), Vec<(String, String)>>,
+}
+
+impl PrecedentTable {
+    /// 判例のID、裁判所名、判決日の西暦での年月日の組から生成する
+    pub fn new(
+        precedents: impl IntoIterator<Item = (String, String, (usize, usize, usize))>,
+    ) -> Self {
+        let mut table: HashMap<(usize, usize, usize), Vec<(String, String)>> = HashMap::new();
+        for (id, court_name, date) in precedents {
+            table.entry(date).or_default().push((court_name, id));
+        }
+        Self { table }
+    }
+
+    /// 引用に当てはまる判例が一つだけの場合にそのIDを返す
+    pub fn resolve(&self, citation: &PrecedentCitation) -> Option<&String> {
+        let candidates = self
+            .table
+            .get(&(citation.year, citation.month, citation.day))?
+            .iter()
+            .filter(|(court_name, _)| court_name.starts_with(&citation.court))
+            // 裁判所名に法廷が含まれている場合のみ法廷でも絞り込む
+            .filter(|(court_name, _)| match &citation.bench {
+                Some(bench) if court_name.contains("法廷") => court_name.contains(bench.as_str()),
+                _ => true,
+            })
+            .collect::<Vec<_>>();
+        match candidates.as_slice() {
+            [(_, id)] => Some(id),
+            _ => None,
+        }
+    }
+}
+
+/// 判決文から引用している判例を取り出し、登録されている判例と対応付ける。
+/// 同じ判例への引用は一つにまとめ、判例自身への引用は除く。
+pub fn extract_cited_precedents(
+    precedent_id: &str,
+    text: &str,
+    precedents: &PrecedentTable,
+) -> Vec<CitedPrecedent> {
+    let mut cited_list: Vec<CitedPrecedent> = Vec::new();
+    for reference in citation::find_precedent_references(text) {
+        let citation = reference.citation;
+        let id = precedents.resolve(&citation).cloned();
+        if id.as_deref() == Some(precedent_id) {
+            continue;
+        }
+        let same = cited_list.iter_mut().find(|c| {
+            let c = &c.citation;
+            c.court == citation.court
+                && (c.year, c.month, c.day) == (citation.year, citation.month, citation.day)
+                && c.kind == citation.kind
+        });
+        match same {
+            Some(cited) => {
+                // 二回目以降の引用にしか判例集の掲載箇所が無い場合は補う
+                if cited.citation.reporter.is_none() {
+                    cited.citation.reporter = citation.reporter;
+                }
+                if cited.citation.bench.is_none() {
+                    cited.citation.bench = citation.bench;
+                }
+                if cited.precedent_id.is_none() {
+                    cited.precedent_id = id;
+                }
+            }
+            None => cited_list.push(CitedPrecedent {
+                precedent_id: id,
+                citation,
+            }),
+        }
+    }
+    cited_list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn law_names() -> LawNameTable {
+        LawNameTable::new([
+            ("民法".to_string(), "129AC0000000089".to_string()),
+            ("刑法".to_string(), "140AC0000000045".to_string()),
+        ])
+    }
+
+    fn statute_summary(statutes: &[PrecedentStatute]) -> Vec<(&str, Option<&str>, StatuteSource)> {
+        statutes
+            .iter()
+            .map(|s| (s.law_name.as_str(), s.article.as_deref(), s.source))
+            .collect()
+    }
+
+    fn kinds(sections: &[PrecedentSectionText]) -> Vec<PrecedentSectionKind> {
+        sections.iter().map(|s| s.kind).collect()
+    }
+
+    #[test]
+    fn split_into_sections() {
+        let text = "上告人　甲\n被上告人　乙\n主　　文\n本件上告を棄却する。\n理　　由\n上告代理人の上告理由について\n所論の点に関する原審の判断は正当である。";
+        let sections = split_precedent_sections(text);
+        assert_eq!(
+            kinds(&sections),
+            vec![
+                PrecedentSectionKind::Preamble,
+                PrecedentSectionKind::MainText,
+                PrecedentSectionKind::Reasons,
+            ]
+        );
+        assert_eq!(sections[0].title, "");
+        assert_eq!(sections[0].text, "上告人　甲\n被上告人　乙");
+        assert_eq!(sections[1].title, "主　　文");
+        assert_eq!(sections[1].text, "主　　文\n本件上告を棄却する。");
+        assert_eq!(sections[2].title, "理　　由");
+    }
+
+    #[test]
+    fn joined_sections_give_original_text() {
+        let text = "原告　甲\n\n主文\n１　被告は，原告に対し，１００万円を支払え。\n\n事実及び理由\n第１　請求\n";
+        let sections = split_precedent_sections(text);
+        assert_eq!(
+            kinds(&sections),
+            vec![
+                PrecedentSectionKind::Preamble,
+                PrecedentSectionKind::MainText,
+                PrecedentSectionKind::FactsAndReasons,
+            ]
+        );
+        let joined = sections
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(joined, text.trim_end_matches('\n'));
+    }
+
+    #[test]
+    fn empty_preamble_is_omitted() {
+        let sections = split_precedent_sections("主文\n本件抗告を棄却する。\n理由\n記録によれば");
+        assert_eq!(
+            kinds(&sections),
+            vec![
+                PrecedentSectionKind::MainText,
+                PrecedentSectionKind::Reasons
+            ]
+        );
+    }
+
+    #[test]
+    fn individual_opinions_start_sections() {
+        let text = "理由\n原判決を破棄する。\n裁判官甲の補足意見は，次のとおりである。\n補足する。\n裁判官乙の反対意見は，次のとおりである。\n反対する。\n裁判官丙の意見は，次のとおりである。\n結論に賛成する。";
+        let sections = split_precedent_sections(text);
+        assert_eq!(
+            kinds(&sections),
+            vec![
+                PrecedentSectionKind::Reasons,
+                PrecedentSectionKind::SupplementaryOpinion,
+                PrecedentSectionKind::DissentingOpinion,
+                PrecedentSectionKind::Opinion,
+            ]
+        );
+        assert_eq!(
+            sections[2].text,
+            "裁判官乙の反対意見は，次のとおりである。\n反対する。"
+        );
+    }
+
+    #[test]
+    fn text_without_heading_is_not_split() {
+        assert!(
+            split_precedent_sections("本件上告を棄却する。\n上告費用は上告人の負担とする。")
+                .is_empty()
+        );
+        assert!(split_precedent_sections("").is_empty());
+        // 本文中に現れる「主文」は見出しとして扱わない
+        assert!(split_precedent_sections("原判決の主文を変更する。").is_empty());
+    }
+
+    #[test]
+    fn section_kind_names_round_trip() {
+        for kind in [
+            PrecedentSectionKind::Preamble,
+            PrecedentSectionKind::MainText,
+            PrecedentSectionKind::FactsAndReasons,
+            PrecedentSectionKind::Facts,
+            PrecedentSectionKind::Reasons,
+            PrecedentSectionKind::SupplementaryOpinion,
+            PrecedentSectionKind::DissentingOpinion,
+            PrecedentSectionKind::Opinion,
+        ] {
+            assert_eq!(PrecedentSectionKind::from_name(kind.name()), Some(kind));
+        }
+        assert_eq!(PrecedentSectionKind::from_name("unknown"), None);
+    }
+
+    #[test]
+    fn statutes_from_reference_articles_come_first() {
+        let statutes = extract_precedent_statutes(
+            Some("民法７０９条，民法７２２条２項"),
+            "刑法第三十五条の趣旨に照らし、民法709条に基づく請求を認める。",
+            &law_names(),
+        );
+        assert_eq!(
+            statute_summary(&statutes),
+            vec![
+                ("民法", Some("709"), StatuteSource::ReferenceArticles),
+                ("民法", Some("722"), StatuteSource::ReferenceArticles),
+                ("刑法", Some("35"), StatuteSource::Text),
+            ]
+        );
+        assert_eq!(statutes[1].paragraph, Some(2));
+        assert_eq!(statutes[0].law_id.as_deref(), Some("129AC0000000089"));
+        assert_eq!(
+            statutes[0].keys(),
+            vec!["129AC0000000089", "129AC0000000089:709"]
+        );
+    }
+
+    #[test]
+    fn statutes_in_text_require_article() {
+        let statutes = extract_precedent_statutes(
+            None,
+            "民法の規定によれば、民法第九十条に反する。",
+            &law_names(),
+        );
+        assert_eq!(
+            statute_summary(&statutes),
+            vec![("民法", Some("90"), StatuteSource::Text)]
+        );
+    }
+
+    #[test]
+    fn law_in_reference_articles_without_article() {
+        let statutes = extract_precedent_statutes(Some("民法"), "", &law_names());
+        assert_eq!(
+            statute_summary(&statutes),
+            vec![("民法", None, StatuteSource::ReferenceArticles)]
+        );
+        assert_eq!(statutes[0].keys(), vec!["129AC0000000089"]);
+    }
+
+    fn precedent_table() -> PrecedentTable {
+        PrecedentTable::new([
+            (
+                "p1".to_string(),
+                "最高裁判所第三小法廷".to_string(),
+                (1968, 12, 24),
+            ),
+            (
+                "p2".to_string(),
+                "最高裁判所第一小法廷".to_string(),
+                (1968, 12, 24),
+            ),
+            (
+                "p3".to_string(),
+                "東京地方裁判所".to_string(),
+                (2020, 1, 30),
+            ),
+            (
+                "p4".to_string(),
+                "東京地方裁判所".to_string(),
+                (2020, 1, 30),
+            ),
+            (
+                "p5".to_string(),
+                "大阪地方裁判所".to_string(),
+                (2020, 1, 30),
+            ),
+        ])
+    }
+
+    fn resolve(text: &str) -> Option<String> {
+        let references = citation::find_precedent_references(text);
+        assert_eq!(references.len(), 1);
+        precedent_table().resolve(&references[0].citation).cloned()
+    }
+
+    #[test]
+    fn resolve_unique_precedent() {
+        assert_eq!(
+            resolve("最高裁昭和43年12月24日第三小法廷判決").as_deref(),
+            Some("p1")
+        );
+        assert_eq!(resolve("大阪地判令和2年1月30日").as_deref(), Some("p5"));
+    }
+
+    #[test]
+    fn resolve_ambiguous_precedent() {
+        // 法廷が書かれていないため、同じ日の最高裁判所の判例を区別できない
+        assert_eq!(resolve("最判昭和43・12・24"), None);
+        assert_eq!(resolve("東京地判令和2年1月30日"), None);
+        assert_eq!(resolve("名古屋地判令和2年1月30日"), None);
+    }
+
+    #[test]
+    fn cited_precedents_are_merged() {
+        let text = "最判昭和43・12・24の趣旨に照らせば（最高裁昭和43年12月24日第三小法廷判決・民集22巻13号3454頁参照）、大阪地判令和2年1月30日と同様に解される。";
+        let cited_list = extract_cited_precedents("p5", text, &precedent_table());
+        assert_eq!(cited_list.len(), 1);
+        let cited = &cited_list[0];
+        assert_eq!(cited.precedent_id.as_deref(), Some("p1"));
+        assert_eq!(cited.citation.bench.as_deref(), Some("第三小法廷"));
+        assert_eq!(cited.citation.reporter.as_ref().map(|r| r.page), Some(3454));
+    }
+}
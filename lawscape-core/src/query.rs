@@ -0,0 +1,694 @@
+//! `"正当な理由" AND 解雇 -懲戒`や`name:民法 text:損害賠償`のような検索式を解析する
+
+use crate::precedent::PrecedentSectionKind;
+use crate::{LawscapeCoreError, LegalDocumentType, escape_filter_value};
+use serde::{Deserialize, Serialize};
+
+/// OR によって分かれる検索の数の上限
+const MAX_QUERY_BRANCHES: usize = 16;
+
+/// 検索語を指定する対象
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryField {
+    /// 法令名。完全一致で絞り込む
+    Name,
+    /// 本文
+    Text,
+    /// 文書の種類。`law`か`precedent`
+    Type,
+    /// 法令ID
+    LawId,
+    /// 判決文の区分。`main_text`や`reasons`など
+    Section,
+}
+
+impl QueryField {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "name" => Some(QueryField::Name),
+            "text" => Some(QueryField::Text),
+            "type" => Some(QueryField::Type),
+            "law_id" => Some(QueryField::LawId),
+            "section" => Some(QueryField::Section),
+            _ => None,
+        }
+    }
+
+    /// 全文検索ではなく絞り込みとして扱うかどうか
+    fn is_filter(&self) -> bool {
+        !matches!(self, QueryField::Text)
+    }
+}
+
+/// 検索式の中の一つの検索語
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct QueryTerm {
+    pub field: Option<QueryField>,
+    pub value: String,
+    /// `"..."`で囲まれたフレーズかどうか
+    pub is_phrase: bool,
+}
+
+/// 検索式の構文木
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum QueryExpr {
+    Term(QueryTerm),
+    Not(Box<QueryExpr>),
+    And(Vec<QueryExpr>),
+    Or(Vec<QueryExpr>),
+}
+
+impl QueryExpr {
+    /// 演算子やフィールドの指定を含まない、検索語を並べただけの式かどうか
+    pub fn is_plain(&self) -> bool {
+        match self {
+            QueryExpr::And(list) => list.iter().all(|expr| expr.is_plain_term()),
+            expr => expr.is_plain_term(),
+        }
+    }
+
+    fn is_plain_term(&self) -> bool {
+        match self {
+            QueryExpr::Term(term) => term.field.is_none() && !term.is_phrase,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Phrase(String),
+    /// `name:`のようなフィールドの指定
+    Field(QueryField),
+    And,
+    Or,
+    Not,
+    LeftParen,
+    RightParen,
+}
+
+fn syntax_error(message: String) -> LawscapeCoreError {
+    LawscapeCoreError::QuerySyntaxError(message)
+}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || c == '(' || c == ')' || c == '"'
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, LawscapeCoreError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(position, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push((position, Token::LeftParen));
+            }
+            ')' => {
+                chars.next();
+                tokens.push((position, Token::RightParen));
+            }
+            '"' => {
+                chars.next();
+                let mut phrase = String::new();
+                let mut is_closed = false;
+                for (_, c) in chars.by_ref() {
+                    if c == '"' {
+                        is_closed = true;
+                        break;
+                    }
+                    phrase.push(c);
+                }
+                if !is_closed {
+                    return Err(syntax_error(format!(
+                        "unclosed quotation mark at {position}"
+                    )));
+                }
+                if phrase.trim().is_empty() {
+                    return Err(syntax_error(format!("empty phrase at {position}")));
+                }
+                tokens.push((position, Token::Phrase(phrase)));
+            }
+            '-' => {
+                chars.next();
+                tokens.push((position, Token::Not));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&(_, c)) = chars.peek() {
+                    if is_separator(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                    if c == ':' {
+                        break;
+                    }
+                }
+                let token = match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => match word.strip_suffix(':') {
+                        Some(name) => {
+                            Token::Field(QueryField::from_name(name).ok_or_else(|| {
+                                syntax_error(format!("unknown field '{name}' at {position}"))
+                            })?)
+                        }
+                        None => Token::Word(word),
+                    },
+                };
+                tokens.push((position, token));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    input_len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, t)| t)
+    }
+
+    /// エラーメッセージに使う現在の位置
+    fn offset(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map(|(p, _)| *p)
+            .unwrap_or(self.input_len)
+    }
+
+    fn parse_or(&mut self) -> Result<QueryExpr, LawscapeCoreError> {
+        let mut list = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            list.push(self.parse_and()?);
+        }
+        Ok(if list.len() == 1 {
+            list.remove(0)
+        } else {
+            QueryExpr::Or(list)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<QueryExpr, LawscapeCoreError> {
+        let mut list = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.position += 1;
+                    list.push(self.parse_unary()?);
+                }
+                // ANDを省略した場合
+                Some(Token::Or) | Some(Token::RightParen) | None => break,
+                Some(_) => list.push(self.parse_unary()?),
+            }
+        }
+        Ok(if list.len() == 1 {
+            list.remove(0)
+        } else {
+            QueryExpr::And(list)
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<QueryExpr, LawscapeCoreError> {
+        if self.peek() == Some(&Token::Not) {
+            self.position += 1;
+            Ok(QueryExpr::Not(Box::new(self.parse_unary()?)))
+        } else {
+            self.parse_primary()
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<QueryExpr, LawscapeCoreError> {
+        let offset = self.offset();
+        let token = self.tokens.get(self.position).map(|(_, t)| t.clone());
+        self.position += 1;
+        match token {
+            Some(Token::LeftParen) => {
+                let expr = self.parse_or()?;
+                if self.peek() != Some(&Token::RightParen) {
+                    return Err(syntax_error(format!(
+                        "unclosed parenthesis opened at {offset}"
+                    )));
+                }
+                self.position += 1;
+                Ok(expr)
+            }
+            Some(Token::Field(field)) => {
+                let token = self.tokens.get(self.position).map(|(_, t)| t.clone());
+                self.position += 1;
+                match token {
+                    Some(Token::Word(value)) => Ok(QueryExpr::Term(QueryTerm {
+                        field: Some(field),
+                        value,
+                        is_phrase: false,
+                    })),
+                    Some(Token::Phrase(value)) => Ok(QueryExpr::Term(QueryTerm {
+                        field: Some(field),
+                        value,
+                        is_phrase: true,
+                    })),
+                    _ => Err(syntax_error(format!(
+                        "a word or phrase is expected after field at {offset}"
+                    ))),
+                }
+            }
+            Some(Token::Word(value)) => Ok(QueryExpr::Term(QueryTerm {
+                field: None,
+                value,
+                is_phrase: false,
+            })),
+            Some(Token::Phrase(value)) => Ok(QueryExpr::Term(QueryTerm {
+                field: None,
+                value,
+                is_phrase: true,
+            })),
+            Some(Token::RightParen) => Err(syntax_error(format!(
+                "unexpected closing parenthesis at {offset}"
+            ))),
+            Some(Token::And) | Some(Token::Or) | Some(Token::Not) => Err(syntax_error(format!(
+                "a word or phrase is expected at {offset}"
+            ))),
+            None => Err(syntax_error(format!("unexpected end of query at {offset}"))),
+        }
+    }
+}
+
+/// 検索式を解析する
+pub fn parse_query(input: &str) -> Result<QueryExpr, LawscapeCoreError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err(syntax_error("query is empty".to_string()));
+    }
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        input_len: input.len(),
+    };
+    let expr = parser.parse_or()?;
+    if parser.position < parser.tokens.len() {
+        return Err(syntax_error(format!(
+            "unexpected token at {}",
+            parser.offset()
+        )));
+    }
+    Ok(expr)
+}
+
+/// 否定を検索語の直前まで移動した検索語
+#[derive(Debug, Clone)]
+struct Literal {
+    term: QueryTerm,
+    is_negative: bool,
+}
+
+/// 構文木をORで結ばれたANDの列に変換する
+fn to_branches(
+    expr: &QueryExpr,
+    is_negative: bool,
+) -> Result<Vec<Vec<Literal>>, LawscapeCoreError> {
+    let branches = match (expr, is_negative) {
+        (QueryExpr::Term(term), _) => vec![vec![Literal {
+            term: term.clone(),
+            is_negative,
+        }]],
+        (QueryExpr::Not(expr), _) => to_branches(expr, !is_negative)?,
+        // ド・モルガンの法則により否定のANDはORに、否定のORはANDになる
+        (QueryExpr::And(list), false) | (QueryExpr::Or(list), true) => {
+            let mut branches = vec![Vec::new()];
+            for expr in list {
+                let expr_branches = to_branches(expr, is_negative)?;
+                let mut new_branches = Vec::new();
+                for branch in branches.iter() {
+                    for expr_branch in expr_branches.iter() {
+                        let mut new_branch = branch.clone();
+                        new_branch.extend(expr_branch.iter().cloned());
+                        new_branches.push(new_branch);
+                    }
+                }
+                branches = new_branches;
+                if MAX_QUERY_BRANCHES < branches.len() {
+                    return Err(too_many_branches());
+                }
+            }
+            branches
+        }
+        (QueryExpr::Or(list), false) | (QueryExpr::And(list), true) => {
+            let mut branches = Vec::new();
+            for expr in list {
+                branches.extend(to_branches(expr, is_negative)?);
+            }
+            branches
+        }
+    };
+    if MAX_QUERY_BRANCHES < branches.len() {
+        return Err(too_many_branches());
+    }
+    Ok(branches)
+}
+
+fn too_many_branches() -> LawscapeCoreError {
+    syntax_error(format!(
+        "query is too complex; it must expand to at most {MAX_QUERY_BRANCHES} OR branches"
+    ))
+}
+
+/// meilisearchへの一回の問い合わせに対応する検索条件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledQuery {
+    /// meilisearchに渡す検索語
+    pub query: String,
+    /// meilisearchのフィルタ式
+    pub filter: Option<String>,
+    /// 本文の検索語のみからなり、本文だけを検索対象にするかどうか
+    pub is_text_only: bool,
+    /// 埋め込みベクトルに変換する文章。否定した語を除き、フレーズの引用符を外したもの
+    pub semantic_query: String,
+}
+
+impl CompiledQuery {
+    /// 検索式として解析せず、検索語をそのままmeilisearchに渡す
+    pub fn keyword(word: &str) -> Self {
+        // meilisearchでも`-`で始まる語は否定として扱われるため、埋め込みベクトルには含めない
+        let semantic_query = word
+            .split_whitespace()
+            .filter(|w| !w.starts_with('-'))
+            .map(|w| w.trim_matches('"'))
+            .filter(|w| !w.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            query: word.to_string(),
+            filter: None,
+            is_text_only: false,
+            semantic_query,
+        }
+    }
+}
+
+fn to_filter_condition(term: &QueryTerm) -> Result<String, LawscapeCoreError> {
+    let value = escape_filter_value(&term.value);
+    match term.field {
+        Some(QueryField::Name) => Ok(format!("name = \"{value}\"")),
+        Some(QueryField::LawId) => Ok(format!("law_id = \"{value}\"")),
+        Some(QueryField::Type) => {
+            let document_type = match term.value.as_str() {
+                "law" => LegalDocumentType::Law,
+                "precedent" => LegalDocumentType::Precedent,
+                v => {
+                    return Err(syntax_error(format!(
+                        "unknown document type '{v}'; expected 'law' or 'precedent'"
+                    )));
+                }
+            };
+            Ok(format!("type = \"{}\"", document_type.type_name()))
+        }
+        Some(QueryField::Section) => {
+            let kind = PrecedentSectionKind::from_name(&term.value).ok_or_else(|| {
+                syntax_error(format!("unknown precedent section '{}'", term.value))
+            })?;
+            Ok(format!("section.kind = \"{}\"", kind.name()))
+        }
+        Some(QueryField::Text) | None => unreachable!(),
+    }
+}
+
+/// 構文木をmeilisearchへの問い合わせに変換する。ORを含む場合は複数の問い合わせになる。
+pub fn compile_query(expr: &QueryExpr) -> Result<Vec<CompiledQuery>, LawscapeCoreError> {
+    to_branches(expr, false)?
+        .into_iter()
+        .map(|literals| {
+            let mut words = Vec::new();
+            let mut positive_words = Vec::new();
+            let mut conditions = Vec::new();
+            let mut is_text_only = true;
+            for literal in literals.iter() {
+                let term = &literal.term;
+                if term.field.is_some_and(|f| f.is_filter()) {
+                    let condition = to_filter_condition(term)?;
+                    conditions.push(if literal.is_negative {
+                        format!("NOT {condition}")
+                    } else {
+                        condition
+                    });
+                } else {
+                    is_text_only &= term.field.is_some();
+                    if !literal.is_negative {
+                        positive_words.push(term.value.as_str());
+                    }
+                    let word = if term.is_phrase {
+                        format!("\"{}\"", term.value.replace('"', ""))
+                    } else {
+                        term.value.clone()
+                    };
+                    words.push(if literal.is_negative {
+                        format!("-{word}")
+                    } else {
+                        word
+                    });
+                }
+            }
+            Ok(CompiledQuery {
+                query: words.join(" "),
+                filter: (!conditions.is_empty()).then(|| conditions.join(" AND ")),
+                is_text_only: is_text_only && !words.is_empty(),
+                semantic_query: positive_words.join(" "),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(value: &str) -> QueryExpr {
+        QueryExpr::Term(QueryTerm {
+            field: None,
+            value: value.to_string(),
+            is_phrase: false,
+        })
+    }
+
+    fn compile(input: &str) -> Vec<CompiledQuery> {
+        compile_query(&parse_query(input).unwrap()).unwrap()
+    }
+
+    /// 構文エラーのメッセージを返す
+    fn syntax_error_message(input: &str) -> String {
+        match parse_query(input).and_then(|expr| compile_query(&expr)) {
+            Err(LawscapeCoreError::QuerySyntaxError(message)) => message,
+            result => panic!("unexpected result for {input:?}: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse_query("解雇 懲戒 OR 無効").unwrap(),
+            QueryExpr::Or(vec![
+                QueryExpr::And(vec![word("解雇"), word("懲戒")]),
+                word("無効"),
+            ])
+        );
+        assert_eq!(
+            parse_query("解雇 OR 懲戒 AND 無効").unwrap(),
+            QueryExpr::Or(vec![
+                word("解雇"),
+                QueryExpr::And(vec![word("懲戒"), word("無効")]),
+            ])
+        );
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        assert_eq!(
+            parse_query("-懲戒 解雇").unwrap(),
+            QueryExpr::And(vec![QueryExpr::Not(Box::new(word("懲戒"))), word("解雇")])
+        );
+        assert_eq!(
+            parse_query("NOT (解雇 OR 懲戒)").unwrap(),
+            QueryExpr::Not(Box::new(QueryExpr::Or(vec![word("解雇"), word("懲戒")])))
+        );
+    }
+
+    #[test]
+    fn parentheses_override_precedence() {
+        assert_eq!(
+            parse_query("(解雇 OR 懲戒) 無効").unwrap(),
+            QueryExpr::And(vec![
+                QueryExpr::Or(vec![word("解雇"), word("懲戒")]),
+                word("無効"),
+            ])
+        );
+    }
+
+    #[test]
+    fn plain_query() {
+        assert!(parse_query("解雇 懲戒").unwrap().is_plain());
+        assert!(!parse_query("\"正当な理由\" 解雇").unwrap().is_plain());
+        assert!(!parse_query("解雇 OR 懲戒").unwrap().is_plain());
+        assert!(!parse_query("text:解雇").unwrap().is_plain());
+    }
+
+    #[test]
+    fn phrase_is_quoted_in_query() {
+        let expr = parse_query("\"正当な 理由\" AND 解雇 -懲戒").unwrap();
+        assert_eq!(
+            expr,
+            QueryExpr::And(vec![
+                QueryExpr::Term(QueryTerm {
+                    field: None,
+                    value: "正当な 理由".to_string(),
+                    is_phrase: true,
+                }),
+                word("解雇"),
+                QueryExpr::Not(Box::new(word("懲戒"))),
+            ])
+        );
+        assert_eq!(
+            compile_query(&expr).unwrap(),
+            vec![CompiledQuery {
+                query: "\"正当な 理由\" 解雇 -懲戒".to_string(),
+                filter: None,
+                is_text_only: false,
+                semantic_query: "正当な 理由 解雇".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn field_terms_become_filters() {
+        assert_eq!(
+            compile("name:民法 text:損害賠償"),
+            vec![CompiledQuery {
+                query: "損害賠償".to_string(),
+                filter: Some("name = \"民法\"".to_string()),
+                is_text_only: true,
+                semantic_query: "損害賠償".to_string(),
+            }]
+        );
+        assert_eq!(
+            compile("type:precedent section:main_text -law_id:129AC0000000089 解雇"),
+            vec![CompiledQuery {
+                query: "解雇".to_string(),
+                filter: Some(
+                    "type = \"Precedent\" AND section.kind = \"main_text\" AND NOT law_id = \"129AC0000000089\""
+                        .to_string()
+                ),
+                is_text_only: false,
+                semantic_query: "解雇".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn field_value_is_escaped() {
+        assert_eq!(
+            compile("name:a\\b")[0].filter.as_deref(),
+            Some("name = \"a\\\\b\"")
+        );
+    }
+
+    #[test]
+    fn or_expands_to_branches() {
+        let queries = compile("(解雇 OR 懲戒) 無効");
+        assert_eq!(
+            queries.iter().map(|q| q.query.as_str()).collect::<Vec<_>>(),
+            vec!["解雇 無効", "懲戒 無効"]
+        );
+    }
+
+    #[test]
+    fn negated_and_expands_by_de_morgan() {
+        // NOT (A AND B) は NOT A OR NOT B になる
+        let queries = compile("-(解雇 懲戒)");
+        assert_eq!(
+            queries.iter().map(|q| q.query.as_str()).collect::<Vec<_>>(),
+            vec!["-解雇", "-懲戒"]
+        );
+        // NOT (A OR B) は NOT A AND NOT B になる
+        let queries = compile("無効 -(解雇 OR type:law)");
+        assert_eq!(
+            queries,
+            vec![CompiledQuery {
+                query: "無効 -解雇".to_string(),
+                filter: Some("NOT type = \"Law\"".to_string()),
+                is_text_only: false,
+                semantic_query: "無効".to_string(),
+            }]
+        );
+        // 二重否定は元に戻る
+        assert_eq!(compile("NOT -解雇")[0].query, "解雇");
+    }
+
+    #[test]
+    fn too_many_branches() {
+        let input = "(a OR b) (c OR d) (e OR f) (g OR h) (i OR j)";
+        assert!(syntax_error_message(input).starts_with("query is too complex"));
+        assert_eq!(compile("(a OR b) (c OR d) (e OR f) (g OR h)").len(), 16);
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let cases = [
+            ("\"正当な理由", "unclosed quotation mark at 0"),
+            ("解雇 \" \"", "empty phrase at 7"),
+            ("foo:解雇", "unknown field 'foo' at 0"),
+            ("(解雇 OR 懲戒", "unclosed parenthesis opened at 0"),
+            (
+                "name: OR 民法",
+                "a word or phrase is expected after field at 0",
+            ),
+            (")解雇", "unexpected closing parenthesis at 0"),
+            ("AND 解雇", "a word or phrase is expected at 0"),
+            ("解雇 AND", "unexpected end of query at 10"),
+            ("", "query is empty"),
+            ("  ", "query is empty"),
+            ("解雇 )", "unexpected token at 7"),
+            (
+                "type:statute",
+                "unknown document type 'statute'; expected 'law' or 'precedent'",
+            ),
+            ("section:foo", "unknown precedent section 'foo'"),
+        ];
+        for (input, message) in cases {
+            assert_eq!(syntax_error_message(input), message, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn keyword_is_not_parsed() {
+        assert_eq!(
+            CompiledQuery::keyword("民法:709 (解雇"),
+            CompiledQuery {
+                query: "民法:709 (解雇".to_string(),
+                filter: None,
+                is_text_only: false,
+                semantic_query: "民法:709 (解雇".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn semantic_query_has_only_positive_terms() {
+        assert_eq!(
+            CompiledQuery::keyword("\"正当な理由\" 解雇 -懲戒").semantic_query,
+            "正当な理由 解雇"
+        );
+        assert_eq!(compile("-(解雇 OR 懲戒) 無効")[0].semantic_query, "無効");
+    }
+}
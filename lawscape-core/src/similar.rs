@@ -0,0 +1,135 @@
+//! 文書の本文から特徴的な語を取り出し、似た文書の検索に使う
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// 特徴的な語の候補とする、漢字またはカタカナの連続
+static TERM_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\p{Han}々〆ヶ]{2,}|[\p{Katakana}ー]{3,}").unwrap());
+
+/// 法令や判決文で頻繁に使われ、文書の特徴を表さない語
+const STOP_TERMS: &[&str] = &[
+    "前項",
+    "前条",
+    "同項",
+    "同条",
+    "次条",
+    "各号",
+    "規定",
+    "場合",
+    "場合等",
+    "前号",
+    "同号",
+    "当該",
+    "以下",
+    "以上",
+    "以外",
+    "本件",
+    "同法",
+    "本法",
+    "事件",
+    "判決",
+    "原判決",
+    "原審",
+    "上告",
+    "上告人",
+    "被上告人",
+    "控訴",
+    "控訴人",
+    "被控訴人",
+    "原告",
+    "被告",
+    "主文",
+    "理由",
+    "事実",
+    "裁判所",
+    "裁判官",
+    "認定",
+    "相当",
+    "必要",
+    "政令",
+    "省令",
+    "施行",
+];
+
+/// 「又は」「及び」のように語の後ろに続けて取り出されてしまう接続の字
+const CONJUNCTION_CHARS: &[char] = &['又', '及', '並', '若'];
+
+/// 語の重みを決める際に考慮する長さの上限
+const MAX_TERM_WEIGHT_LENGTH: usize = 6;
+
+/// 本文から特徴的な語を重要なものから順に最大`size`個取り出す。
+/// 出現回数と語の長さから重みを求め、同じ重みの場合は先に出現したものを優先する。
+pub fn extract_salient_terms(text: &str, size: usize) -> Vec<String> {
+    // 語ごとの出現回数と最初に出現した位置
+    let mut term_counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for m in TERM_REGEX.find_iter(text) {
+        let term = m.as_str().trim_end_matches(CONJUNCTION_CHARS);
+        if term.chars().count() < 2 || STOP_TERMS.contains(&term) {
+            continue;
+        }
+        let entry = term_counts.entry(term).or_insert((0, m.start()));
+        entry.0 += 1;
+    }
+    let mut terms = term_counts
+        .into_iter()
+        .map(|(term, (count, first))| {
+            let weight = count * term.chars().count().min(MAX_TERM_WEIGHT_LENGTH);
+            (term, weight, first)
+        })
+        .collect::<Vec<_>>();
+    terms.sort_by(|(_, w1, f1), (_, w2, f2)| w2.cmp(w1).then_with(|| f1.cmp(f2)));
+    terms
+        .into_iter()
+        .take(size)
+        .map(|(term, _, _)| term.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frequent_and_long_terms_come_first() {
+        let text = "損害賠償の請求。損害賠償の額。不法行為による損害賠償。過失";
+        assert_eq!(
+            extract_salient_terms(text, 3),
+            vec!["損害賠償", "不法行為", "請求"]
+        );
+    }
+
+    #[test]
+    fn same_weight_is_ordered_by_first_occurrence() {
+        assert_eq!(
+            extract_salient_terms("債務者、債権者", 2),
+            vec!["債務者", "債権者"]
+        );
+    }
+
+    #[test]
+    fn stop_terms_and_conjunctions_are_removed() {
+        // 「前項」「場合」は取り出さず、「賃借人又」の「又」は取り除く
+        assert_eq!(
+            extract_salient_terms("前項の場合において、賃借人又は転借人は", 10),
+            vec!["賃借人", "転借人"]
+        );
+    }
+
+    #[test]
+    fn katakana_terms_need_three_characters() {
+        assert_eq!(
+            extract_salient_terms("コンピュータ・プログラムとデータ", 10),
+            vec!["コンピュータ", "プログラム", "データ"]
+        );
+        assert!(extract_salient_terms("ガス", 10).is_empty());
+    }
+
+    #[test]
+    fn size_limits_terms() {
+        assert_eq!(extract_salient_terms("著作権、著作者、著作物", 2).len(), 2);
+        assert!(extract_salient_terms("", 10).is_empty());
+        assert!(extract_salient_terms("著作権", 0).is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+//! 条文の項・号・号の細分の構造を扱う
+
+use serde::{Deserialize, Serialize};
+
+/// 条文の中での文章の位置
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+pub struct TextPosition {
+    /// 項番号。項が一つだけの条文では`None`のこともある
+    pub paragraph: Option<String>,
+    /// 号番号と号の細分の番号を外側から順に並べたもの。項の本文の場合は空
+    pub items: Vec<String>,
+}
+
+/// 号または号の細分
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct ArticleItem {
+    /// 号番号。「第三号の二」は`3_2`のように表す
+    pub num: String,
+    pub text: String,
+    /// 号の細分
+    pub children: Vec<ArticleItem>,
+}
+
+/// 項
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct ArticleParagraph {
+    /// 項番号
+    pub num: String,
+    pub text: String,
+    pub items: Vec<ArticleItem>,
+}
+
+/// 項が一つだけで番号が付いていない条文に使う項番号
+const DEFAULT_PARAGRAPH_NUM: &str = "1";
+
+impl ArticleItem {
+    fn push_text(&self, lines: &mut Vec<String>) {
+        lines.push(self.text.clone());
+        for child in self.children.iter() {
+            child.push_text(lines);
+        }
+    }
+}
+
+impl ArticleParagraph {
+    /// 号も含めた項全体の本文
+    pub fn full_text(&self) -> String {
+        let mut lines = vec![self.text.clone()];
+        for item in self.items.iter() {
+            item.push_text(&mut lines);
+        }
+        lines.retain(|line| !line.is_empty());
+        lines.join("\n")
+    }
+
+    /// 号番号を指定して号を探す
+    pub fn find_item(&self, num: &str) -> Option<&ArticleItem> {
+        self.items.iter().find(|item| item.num == num)
+    }
+}
+
+/// 項番号と号番号を指定して項と号を探す。号番号を指定した場合は号が無ければ`None`を返す
+pub fn locate_provision(
+    paragraphs: &[ArticleParagraph],
+    paragraph: usize,
+    item: Option<usize>,
+) -> Option<(&ArticleParagraph, Option<&ArticleItem>)> {
+    let paragraph = paragraphs.iter().find(|p| p.num == paragraph.to_string())?;
+    let item = match item {
+        Some(item) => Some(paragraph.find_item(&item.to_string())?),
+        None => None,
+    };
+    Some((paragraph, item))
+}
+
+/// 号の細分をたどり、`path`の位置にある号を返す。無い場合は作る
+fn item_at<'a>(items: &'a mut Vec<ArticleItem>, path: &[String]) -> Option<&'a mut ArticleItem> {
+    let (num, rest) = path.split_first()?;
+    let index = match items.iter().position(|item| &item.num == num) {
+        Some(index) => index,
+        None => {
+            items.push(ArticleItem {
+                num: num.clone(),
+                text: String::new(),
+                children: Vec::new(),
+            });
+            items.len() - 1
+        }
+    };
+    let item = &mut items[index];
+    if rest.is_empty() {
+        Some(item)
+    } else {
+        item_at(&mut item.children, rest)
+    }
+}
+
+/// 文章とその位置の一覧から項と号の構造を組み立てる。項は一覧に現れた順に並ぶ
+pub fn build_paragraphs(text_list: &[(TextPosition, String)]) -> Vec<ArticleParagraph> {
+    let mut paragraphs: Vec<ArticleParagraph> = Vec::new();
+    for (position, text) in text_list.iter() {
+        let num = position
+            .paragraph
+            .clone()
+            .unwrap_or_else(|| DEFAULT_PARAGRAPH_NUM.to_string());
+        let index = match paragraphs.iter().position(|p| p.num == num) {
+            Some(index) => index,
+            None => {
+                paragraphs.push(ArticleParagraph {
+                    num,
+                    text: String::new(),
+                    items: Vec::new(),
+                });
+                paragraphs.len() - 1
+            }
+        };
+        let paragraph = &mut paragraphs[index];
+        let target = match item_at(&mut paragraph.items, &position.items) {
+            Some(item) => &mut item.text,
+            None => &mut paragraph.text,
+        };
+        if !target.is_empty() {
+            target.push('\n');
+        }
+        target.push_str(text);
+    }
+    paragraphs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(paragraph: Option<&str>, items: &[&str]) -> TextPosition {
+        TextPosition {
+            paragraph: paragraph.map(|p| p.to_string()),
+            items: items.iter().map(|i| i.to_string()).collect(),
+        }
+    }
+
+    /// 民法第七百九条と第七百十条を一つの条に見立てた、号と号の細分を含む条文
+    fn fixture() -> Vec<ArticleParagraph> {
+        build_paragraphs(&[
+            (
+                position(Some("1"), &[]),
+                "故意又は過失によって他人の権利又は法律上保護される利益を侵害した者は、これによって生じた損害を賠償する責任を負う。".to_string(),
+            ),
+            (
+                position(Some("2"), &[]),
+                "前項の規定は、次に掲げる場合に準用する。".to_string(),
+            ),
+            (position(Some("2"), &["1"]), "他人の身体を害した場合".to_string()),
+            (position(Some("2"), &["2"]), "他人の財産権を侵害した場合".to_string()),
+            (position(Some("2"), &["2", "イ"]), "所有権".to_string()),
+            (position(Some("2"), &["2", "ロ"]), "占有権".to_string()),
+        ])
+    }
+
+    #[test]
+    fn build_paragraphs_with_items_and_sub_items() {
+        let paragraphs = fixture();
+        assert_eq!(
+            paragraphs
+                .iter()
+                .map(|p| p.num.as_str())
+                .collect::<Vec<_>>(),
+            vec!["1", "2"]
+        );
+        assert!(paragraphs[0].items.is_empty());
+        let items = &paragraphs[1].items;
+        assert_eq!(
+            items.iter().map(|i| i.num.as_str()).collect::<Vec<_>>(),
+            vec!["1", "2"]
+        );
+        assert_eq!(items[1].text, "他人の財産権を侵害した場合");
+        assert_eq!(
+            items[1]
+                .children
+                .iter()
+                .map(|i| (i.num.as_str(), i.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("イ", "所有権"), ("ロ", "占有権")]
+        );
+        assert_eq!(
+            paragraphs[1].full_text(),
+            "前項の規定は、次に掲げる場合に準用する。\n他人の身体を害した場合\n他人の財産権を侵害した場合\n所有権\n占有権"
+        );
+    }
+
+    #[test]
+    fn paragraph_without_number_is_first_paragraph() {
+        let paragraphs = build_paragraphs(&[
+            (position(None, &[]), "一行目".to_string()),
+            (position(None, &[]), "二行目".to_string()),
+        ]);
+        assert_eq!(paragraphs.len(), 1);
+        assert_eq!(paragraphs[0].num, DEFAULT_PARAGRAPH_NUM);
+        assert_eq!(paragraphs[0].text, "一行目\n二行目");
+    }
+
+    #[test]
+    fn sub_item_before_item_creates_item() {
+        let paragraphs =
+            build_paragraphs(&[(position(Some("1"), &["3", "イ"]), "細分".to_string())]);
+        let item = paragraphs[0].find_item("3").unwrap();
+        assert_eq!(item.text, "");
+        assert_eq!(item.children[0].num, "イ");
+        assert_eq!(paragraphs[0].full_text(), "細分");
+    }
+
+    #[test]
+    fn locate_paragraph() {
+        // 第709条第1項
+        let paragraphs = fixture();
+        let (paragraph, item) = locate_provision(&paragraphs, 1, None).unwrap();
+        assert_eq!(paragraph.num, "1");
+        assert!(paragraph.text.starts_with("故意又は過失"));
+        assert_eq!(item, None);
+    }
+
+    #[test]
+    fn locate_item() {
+        let paragraphs = fixture();
+        let (paragraph, item) = locate_provision(&paragraphs, 2, Some(2)).unwrap();
+        assert_eq!(paragraph.num, "2");
+        assert_eq!(item.unwrap().text, "他人の財産権を侵害した場合");
+    }
+
+    #[test]
+    fn locate_missing_provision() {
+        let paragraphs = fixture();
+        assert!(locate_provision(&paragraphs, 3, None).is_none());
+        assert!(locate_provision(&paragraphs, 1, Some(1)).is_none());
+        assert!(locate_provision(&paragraphs, 2, Some(3)).is_none());
+    }
+}
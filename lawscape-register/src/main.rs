@@ -6,9 +6,21 @@ use jplaw_data_types::{
     listup::{LawInfo, PrecedentInfo},
     precedent::PrecedentData,
 };
-use lawscape_core::{Law, LegalDocument, LegalDocumentsRegistory, Precedent};
+use lawscape_core::citation::{self, LawNameTable};
+use lawscape_core::definition;
+use lawscape_core::delegation;
+use lawscape_core::embedding::HashedNgramEmbedder;
+use lawscape_core::law_metadata::{self, ArticleMetadataTable};
+use lawscape_core::patch::{self, DateBoundary, PatchSelectionReport};
+use lawscape_core::precedent::{self, PrecedentTable};
+use lawscape_core::structure::{self, TextPosition};
+use lawscape_core::{
+    Law, LawStatus, LegalDocument, LegalDocumentsRegistory, Precedent, Suggestion,
+};
 use regex::Regex;
+use serde::Deserialize;
 use std::path::Path;
+use std::sync::Arc;
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
 use tokio_stream::StreamExt;
@@ -94,6 +106,129 @@ struct AppArg {
     /// 法律を登録する際の基準とする日付
     #[arg(long)]
     pub date: String,
+    /// 基準とする日付に施行される版を含める（inclusive）か、含めない（exclusive）か
+    #[arg(long, default_value = "inclusive")]
+    pub date_boundary: DateBoundary,
+    /// 基準とする日付に施行されている版が無かった法令の一覧を書き出すJSONファイル
+    #[arg(long)]
+    pub report: Option<String>,
+    /// 文書に埋め込みベクトルを付けて登録し、ハイブリッド検索ができるようにする
+    #[arg(long)]
+    pub embedding: bool,
+    /// 法令を条ごとではなく項ごとに分けて登録する
+    #[arg(long)]
+    pub paragraph_level: bool,
+    /// 廃止された法令を登録しない
+    #[arg(long)]
+    pub exclude_repealed: bool,
+    /// 基準とする日付より後に施行される版も登録する
+    #[arg(long)]
+    pub include_future: bool,
+}
+
+/// 判例データのうち`PrecedentData`に含まれない情報
+#[derive(Debug, Clone, Default, Deserialize)]
+struct PrecedentMetadata {
+    /// 参照法条
+    #[serde(default)]
+    ref_law: Option<String>,
+}
+
+/// 法令の指定した版のXMLを読み込み、条文の一覧を生成する
+async fn parse_law_articles(
+    law_folder: &str,
+    law_index: &LawInfo,
+    patch: &LawPatchInfo,
+    status: LawStatus,
+    paragraph_level: bool,
+    law_names: &LawNameTable,
+) -> Result<Vec<Law>> {
+    let id = &law_index.id;
+    let id_str = format!("{id}");
+    let name = &law_index.name;
+    let law_file_name = patch.to_file_path();
+    // 施行前の版は施行中の版と別の文書として登録する
+    let id_prefix = match status {
+        LawStatus::NotYetEffective => format!("{id}_future"),
+        _ => id_str.clone(),
+    };
+    let law_file_path = Path::new(law_folder);
+    let law_file_path = law_file_path.join(&law_file_name);
+    let law_file_path = law_file_path.join(format!("{law_file_name}.xml"));
+    let mut law_xml_f = File::open(law_file_path).await?;
+    let mut law_xml_buf = Vec::new();
+    law_xml_f.read_to_end(&mut law_xml_buf).await?;
+    let law_data = japanese_law_xml_schema::parse_xml(&law_xml_buf)?;
+    // 見出しや編・章などの情報は解析済みの法令XMLから取り出す
+    let (law_info, article_info_list) = law_metadata::parse_law_metadata(&law_data);
+    let mut article_info_table = ArticleMetadataTable::new(article_info_list);
+    // 「〇〇法施行令」のような法令名から委任元の法令を求める
+    let delegated_by =
+        delegation::find_delegating_law(name, law_names).filter(|parent_id| parent_id != &id_str);
+    let article_list = article::article_list_from_lawbody(&id_str, name, &law_data.law_body)
+        .iter()
+        .enumerate()
+        .flat_map(|(position, result)| {
+            let text_list = article::text_list_from_paragraph(&result.result)
+                .iter()
+                .map(|(index, text)| {
+                    let text_position = TextPosition {
+                        paragraph: index.paragraph.clone(),
+                        items: index
+                            .item
+                            .iter()
+                            .chain(index.sub_item.iter())
+                            .cloned()
+                            .collect(),
+                    };
+                    (text_position, text.clone())
+                })
+                .collect::<Vec<(TextPosition, String)>>();
+            let text = text_list
+                .iter()
+                .map(|(_, text)| text.clone())
+                .collect::<Vec<String>>()
+                .join("\n");
+            let article_info = article_info_table
+                .take(&result.article_index.article)
+                .unwrap_or_default();
+            let reference_keys = citation::find_statute_keys(&text, law_names, Some(&id_str));
+            let delegated_law_types = delegation::find_delegated_law_types(&text);
+            let paragraphs = structure::build_paragraphs(&text_list);
+            let definitions = definition::extract_definitions(&paragraphs);
+            let law = Law {
+                id: format!("{id_prefix}_{position}"),
+                law_id: id.clone(),
+                name: name.clone(),
+                index: result.article_index.clone(),
+                text,
+                position,
+                paragraphs,
+                paragraph: None,
+                caption: article_info.caption,
+                chapter_path: article_info.chapter_path,
+                suppl_provision: article_info.suppl_provision,
+                law_num: law_info.law_num.clone(),
+                law_type: law_info.law_type,
+                promulgation_date: law_info.promulgation_date.clone(),
+                promulgation_year: law_info.promulgation_year,
+                status,
+                effective_date: Some(patch.patch_date.clone()),
+                date_number: Some(lawscape_core::date_to_number(&patch.patch_date)),
+                reference_keys,
+                delegated_by: delegated_by.clone(),
+                delegated_law_types,
+                defined_terms: definition::defined_terms(&definitions),
+                definitions,
+            };
+            if paragraph_level {
+                law.into_paragraphs()
+            } else {
+                vec![law]
+            }
+        })
+        .collect::<Vec<Law>>();
+    Ok(article_list)
 }
 
 #[tokio::main]
@@ -104,8 +239,14 @@ async fn main() -> Result<()> {
 
     let law_date = parse_date(&app_args.date)?;
 
-    let legal_document_registory =
+    let mut legal_document_registory =
         LegalDocumentsRegistory::new(&app_args.meilisearch_url, &app_args.meilisearch_master_key)?;
+    if app_args.embedding {
+        legal_document_registory =
+            legal_document_registory.with_embedder(Arc::new(HashedNgramEmbedder::default()));
+    }
+
+    legal_document_registory.update_settings().await?;
 
     info!("[START] parsing law data");
     // 検索エンジン用の法令データを生成する
@@ -113,62 +254,94 @@ async fn main() -> Result<()> {
     let mut law_index_buf = Vec::new();
     law_index_f.read_to_end(&mut law_index_buf).await?;
     let law_index_list = serde_json::from_slice::<Vec<LawInfo>>(&law_index_buf)?;
+    // 判例が参照している法令を対応付けるために使う
+    let law_names = LawNameTable::new(
+        law_index_list
+            .iter()
+            .map(|law_index| (law_index.name.clone(), format!("{}", law_index.id))),
+    );
     let mut law_index_stream = tokio_stream::iter(law_index_list);
+    let mut report = PatchSelectionReport::new(&law_date, app_args.date_boundary);
     while let Some(law_index) = law_index_stream.next().await {
         let id = &law_index.id;
         let id_str = format!("{id}");
         let name = &law_index.name;
 
         // 与えられた日付時点で施行されている物を探す
-        let mut patch_file: Option<LawPatchInfo> = None;
-        for law_patch in law_index.patch.iter() {
-            if law_patch.patch_date < law_date {
-                if let Some(p) = &patch_file {
-                    if law_patch.patch_date > p.patch_date {
-                        patch_file = Some(law_patch.clone())
-                    }
-                } else {
-                    patch_file = Some(law_patch.clone())
-                }
-            }
-        }
-        if let Some(law_file_name) = patch_file.map(|p| p.to_file_path()) {
+        let patch_file = report.select(&id_str, name, &law_index.patch).cloned();
+        let future_patch_file = if app_args.include_future {
+            patch::select_future_version(&law_index.patch, &law_date, app_args.date_boundary)
+                .cloned()
+        } else {
+            None
+        };
+        if let Some(patch) = patch_file {
             info!("[START] parsing law: {id_str}");
-            let law_file_path = Path::new(&app_args.law_folder);
-            let law_file_path = law_file_path.join(&law_file_name);
-            let law_file_path = law_file_path.join(format!("{law_file_name}.xml"));
-            let mut law_xml_f = File::open(law_file_path).await?;
-            let mut law_xml_buf = Vec::new();
-            law_xml_f.read_to_end(&mut law_xml_buf).await?;
-            let law_data = japanese_law_xml_schema::parse_xml(&law_xml_buf)?;
-            let article_list =
-                article::article_list_from_lawbody(&id_str, name, &law_data.law_body)
-                    .iter()
-                    .map(|result| {
-                        let text = article::text_list_from_paragraph(&result.result)
-                            .iter()
-                            .map(|(_, text)| text.clone())
-                            .collect::<Vec<String>>()
-                            .join("\n");
-                        LegalDocument::Law(Law {
-                            id: format!("{id}"),
-                            law_id: id.clone(),
-                            name: name.clone(),
-                            index: result.article_index.clone(),
-                            text,
-                        })
-                    })
-                    .collect::<Vec<LegalDocument>>();
+            let law_list = parse_law_articles(
+                &app_args.law_folder,
+                &law_index,
+                &patch,
+                LawStatus::InForce,
+                app_args.paragraph_level,
+                &law_names,
+            )
+            .await?;
+            let status = LawStatus::detect(&law_list);
             info!("[END] parsing law: {id_str}");
+            if status == LawStatus::Repealed && app_args.exclude_repealed {
+                info!("skip repealed law: {id_str}");
+            } else {
+                let article_list = law_list
+                    .into_iter()
+                    .map(|law| LegalDocument::Law(Law { status, ..law }))
+                    .collect::<Vec<LegalDocument>>();
+                info!(
+                    "[START] register law: {id_str}, article size: {}",
+                    article_list.len()
+                );
+                legal_document_registory.add_data(&article_list).await?;
+                legal_document_registory
+                    .add_suggestions(&[Suggestion::from_law(id, name)])
+                    .await?;
+                info!("[END] register law: {id_str}");
+            }
+        }
+        // まだ施行されていない版も別の文書として登録する
+        if let Some(patch) = future_patch_file {
+            info!("[START] parsing future law: {id_str}");
+            let article_list = parse_law_articles(
+                &app_args.law_folder,
+                &law_index,
+                &patch,
+                LawStatus::NotYetEffective,
+                app_args.paragraph_level,
+                &law_names,
+            )
+            .await?
+            .into_iter()
+            .map(LegalDocument::Law)
+            .collect::<Vec<LegalDocument>>();
+            info!("[END] parsing future law: {id_str}");
             info!(
-                "[START] register law: {id_str}, article size: {}",
+                "[START] register future law: {id_str}, article size: {}",
                 article_list.len()
             );
             legal_document_registory.add_data(&article_list).await?;
-            info!("[END] register law: {id_str}");
+            info!("[END] register future law: {id_str}");
         }
     }
     info!("[END] parsing law data");
+    info!(
+        "law versions selected: {}, no version in force: {}",
+        report.selected_count,
+        report.missing.len()
+    );
+    for missing in report.missing.iter() {
+        info!("no version in force: {} {}", missing.law_id, missing.name);
+    }
+    if let Some(report_path) = &app_args.report {
+        tokio::fs::write(report_path, serde_json::to_vec_pretty(&report)?).await?;
+    }
 
     // 検索エンジン用の判例データを生成する
     info!("[START] parsing precedent data");
@@ -178,6 +351,14 @@ async fn main() -> Result<()> {
         .read_to_end(&mut precedent_index_buf)
         .await?;
     let precedent_index_list = serde_json::from_slice::<Vec<PrecedentInfo>>(&precedent_index_buf)?;
+    // 判例が引用している判例を対応付けるために使う
+    let precedent_table = PrecedentTable::new(precedent_index_list.iter().map(|info| {
+        (
+            info.file_name(),
+            info.court_name.clone(),
+            lawscape_core::date_to_ad(&info.date),
+        )
+    }));
     let mut precedent_index_stream = tokio_stream::iter(precedent_index_list);
     while let Some(precedent_info) = precedent_index_stream.next().await {
         let file_path = Path::new(&app_args.precedent_folder).join(precedent_info.file_name());
@@ -185,15 +366,35 @@ async fn main() -> Result<()> {
         let mut precedent_buf = Vec::new();
         precedent_file.read_to_end(&mut precedent_buf).await?;
         let precedent = serde_json::from_slice::<PrecedentData>(&precedent_buf)?;
+        let metadata = serde_json::from_slice::<PrecedentMetadata>(&precedent_buf)?;
         info!("[END] parsing precedent: {}", precedent_info.lawsuit_id);
         info!("[START] register precedent: {}", precedent_info.lawsuit_id);
         if let Some(text) = precedent.contents {
-            let v = [LegalDocument::Precedent(Precedent {
-                id: precedent_info.file_name(),
-                info: precedent_info.clone(),
-                text,
-            })];
+            let statutes = precedent::extract_precedent_statutes(
+                metadata.ref_law.as_deref(),
+                &text,
+                &law_names,
+            );
+            let cited_precedents = precedent::extract_cited_precedents(
+                &precedent_info.file_name(),
+                &text,
+                &precedent_table,
+            );
+            let precedent =
+                Precedent::new(precedent_info.file_name(), precedent_info.clone(), text)
+                    .with_statutes(statutes)
+                    .with_cited_precedents(cited_precedents);
+            let suggestion = Suggestion::from_precedent(&precedent);
+            // 主文や理由などの区分ごとに分けて登録する
+            let v = precedent
+                .into_sections()
+                .into_iter()
+                .map(LegalDocument::Precedent)
+                .collect::<Vec<LegalDocument>>();
             legal_document_registory.add_data(&v).await?;
+            legal_document_registory
+                .add_suggestions(&[suggestion])
+                .await?;
         }
         info!("[END] register precedent: {}", precedent_info.lawsuit_id);
     }
@@ -205,3 +406,34 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 判例データの一部
+    const PRECEDENT_FIXTURE: &str = r#"{
+        "case_number": "昭和41(オ)1223",
+        "case_name": "損害賠償請求",
+        "court_name": "最高裁判所第三小法廷",
+        "ref_law": "民法７０９条，民法７２２条２項",
+        "contents": "主文\n本件上告を棄却する。"
+    }"#;
+
+    #[test]
+    fn precedent_metadata_reads_reference_articles() {
+        let metadata = serde_json::from_str::<PrecedentMetadata>(PRECEDENT_FIXTURE).unwrap();
+        assert_eq!(
+            metadata.ref_law.as_deref(),
+            Some("民法７０９条，民法７２２条２項")
+        );
+    }
+
+    #[test]
+    fn precedent_metadata_without_reference_articles() {
+        let metadata = serde_json::from_str::<PrecedentMetadata>(r#"{"ref_law": null}"#).unwrap();
+        assert_eq!(metadata.ref_law, None);
+        let metadata = serde_json::from_str::<PrecedentMetadata>("{}").unwrap();
+        assert_eq!(metadata.ref_law, None);
+    }
+}